@@ -0,0 +1,285 @@
+/**
+ * CodecovApi is the set of operations of Client as a trait,
+ * so that code consuming coverage data can be generic over the backend
+ * (Client, testing::MockApi or an implementation of your own).
+ *
+ * ```
+ * use codecov::api::CodecovApi;
+ * use codecov::author::Author;
+ * use codecov::errors::Error;
+ * use codecov::revision::Revision;
+ *
+ * fn main_coverage(api: &impl CodecovApi, author: &Author) -> Result<f64, Error> {
+ *     let totals = api.get_totals(author, &Revision::Branch("main".to_string()))?;
+ *     Ok(totals.coverage)
+ * }
+ * ```
+ */
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::time::Duration;
+
+use crate::author::Author;
+use crate::branch_detail::{BranchDetailAPIResponse, Report};
+use crate::branches::BranchesAPIResponse;
+use crate::commits::{Commit, CommitHistory, CommitsAPIResponse};
+use crate::errors::Error;
+use crate::gate::{Gate, GateResult};
+use crate::owner::Owner;
+use crate::repos::Repo;
+use crate::revision::Revision;
+use crate::totals::Totals;
+use crate::uploads::Upload;
+use crate::wait::{self, WaitOptions};
+use crate::Client;
+
+pub trait CodecovApi {
+    fn get_all_repos(&self, owner: &Owner) -> Result<Vec<Repo>, Error>;
+
+    fn get_commits(&self, author: &Author) -> Result<CommitsAPIResponse, Error>;
+
+    fn get_commit_history(&self, author: &Author) -> Result<CommitHistory, Error>;
+
+    fn get_commit(&self, author: &Author, commitid: &str) -> Result<Commit, Error>;
+
+    fn get_commit_uploads(&self, author: &Author, commitid: &str) -> Result<Vec<Upload>, Error>;
+
+    fn get_branches(&self, author: &Author) -> Result<BranchesAPIResponse, Error>;
+
+    fn get_branch_detail(
+        &self,
+        author: &Author,
+        branch_name: &str,
+    ) -> Result<BranchDetailAPIResponse, Error>;
+
+    /**
+     * Returns the totals of the head commit of a branch, or of a given commit.
+     */
+    fn get_totals(&self, author: &Author, revision: &Revision) -> Result<Totals, Error> {
+        match revision {
+            Revision::Branch(name) => match self.get_branch_detail(author, name)? {
+                BranchDetailAPIResponse::Success(detail) => Ok(detail.head_commit.totals),
+                BranchDetailAPIResponse::NotFound(not_found) => {
+                    Err(Error::NotFoundError(not_found.detail))
+                }
+            },
+            Revision::Commit(commitid) => Ok(self.get_commit(author, commitid)?.totals),
+        }
+    }
+
+    /**
+     * Returns the report (totals and file totals) of the head commit of a branch, or of a given commit.
+     */
+    fn get_report(&self, author: &Author, revision: &Revision) -> Result<Report, Error> {
+        match revision {
+            Revision::Branch(name) => match self.get_branch_detail(author, name)? {
+                BranchDetailAPIResponse::Success(detail) => Ok(detail.head_commit.report),
+                BranchDetailAPIResponse::NotFound(not_found) => {
+                    Err(Error::NotFoundError(not_found.detail))
+                }
+            },
+            Revision::Commit(commitid) => match self.get_commit(author, commitid)?.report {
+                Some(report) => Ok(report),
+                None => Err(Error::NotFoundError(format!(
+                    "commit {} has no report",
+                    commitid
+                ))),
+            },
+        }
+    }
+
+    /**
+     * Fetches the head report and the base totals, then applies the gate.
+     */
+    fn check_gate(
+        &self,
+        gate: &Gate,
+        author: &Author,
+        head: &Revision,
+        base: Option<&Revision>,
+    ) -> Result<GateResult, Error> {
+        let head = self.get_report(author, head)?;
+        let base = match base {
+            Some(base) => Some(self.get_totals(author, base)?),
+            None => None,
+        };
+        Ok(gate.check(&head, base.as_ref()))
+    }
+
+    /**
+     * Polls a commit until Codecov has finished processing it (complete, error or skipped).
+     * A commit that is not found yet (e.g. right after the upload) is polled again.
+     * Returns Error::TimeoutError when the commit is still pending after timeout.
+     */
+    fn wait_for_commit(
+        &self,
+        author: &Author,
+        commitid: &str,
+        timeout: Duration,
+    ) -> Result<Commit, Error> {
+        self.wait_for_commit_with(author, commitid, &WaitOptions::new(timeout))
+    }
+
+    /**
+     * wait_for_commit with a custom backoff and a cancellation hook.
+     */
+    fn wait_for_commit_with(
+        &self,
+        author: &Author,
+        commitid: &str,
+        options: &WaitOptions,
+    ) -> Result<Commit, Error> {
+        wait::poll(
+            || self.get_commit(author, commitid),
+            options,
+            std::thread::sleep,
+        )
+    }
+}
+
+impl CodecovApi for Client {
+    fn get_all_repos(&self, owner: &Owner) -> Result<Vec<Repo>, Error> {
+        Client::get_all_repos(self, owner)
+    }
+
+    fn get_commits(&self, author: &Author) -> Result<CommitsAPIResponse, Error> {
+        Client::get_commits(self, author)
+    }
+
+    fn get_commit_history(&self, author: &Author) -> Result<CommitHistory, Error> {
+        Client::get_commit_history(self, author)
+    }
+
+    fn get_commit(&self, author: &Author, commitid: &str) -> Result<Commit, Error> {
+        Client::get_commit(self, author, commitid)
+    }
+
+    fn get_commit_uploads(&self, author: &Author, commitid: &str) -> Result<Vec<Upload>, Error> {
+        Client::get_commit_uploads(self, author, commitid)
+    }
+
+    fn get_branches(&self, author: &Author) -> Result<BranchesAPIResponse, Error> {
+        Client::get_branches(self, author)
+    }
+
+    fn get_branch_detail(
+        &self,
+        author: &Author,
+        branch_name: &str,
+    ) -> Result<BranchDetailAPIResponse, Error> {
+        Client::get_branch_detail(self, author, branch_name)
+    }
+}
+
+/**
+ * AsyncCodecovApi is the async variant of CodecovApi, implemented by async_client::AsyncClient.
+ * It is enabled by the `async` feature.
+ */
+#[cfg(feature = "async")]
+pub trait AsyncCodecovApi {
+    fn get_all_repos(&self, owner: &Owner)
+        -> impl Future<Output = Result<Vec<Repo>, Error>> + Send;
+
+    fn get_commits(
+        &self,
+        author: &Author,
+    ) -> impl Future<Output = Result<CommitsAPIResponse, Error>> + Send;
+
+    fn get_commit_history(
+        &self,
+        author: &Author,
+    ) -> impl Future<Output = Result<CommitHistory, Error>> + Send;
+
+    fn get_commit(
+        &self,
+        author: &Author,
+        commitid: &str,
+    ) -> impl Future<Output = Result<Commit, Error>> + Send;
+
+    fn get_commit_uploads(
+        &self,
+        author: &Author,
+        commitid: &str,
+    ) -> impl Future<Output = Result<Vec<Upload>, Error>> + Send;
+
+    fn get_branches(
+        &self,
+        author: &Author,
+    ) -> impl Future<Output = Result<BranchesAPIResponse, Error>> + Send;
+
+    fn get_branch_detail(
+        &self,
+        author: &Author,
+        branch_name: &str,
+    ) -> impl Future<Output = Result<BranchDetailAPIResponse, Error>> + Send;
+
+    fn get_totals(
+        &self,
+        author: &Author,
+        revision: &Revision,
+    ) -> impl Future<Output = Result<Totals, Error>> + Send;
+
+    fn get_report(
+        &self,
+        author: &Author,
+        revision: &Revision,
+    ) -> impl Future<Output = Result<Report, Error>> + Send;
+
+    fn check_gate(
+        &self,
+        gate: &Gate,
+        author: &Author,
+        head: &Revision,
+        base: Option<&Revision>,
+    ) -> impl Future<Output = Result<GateResult, Error>> + Send;
+
+    fn wait_for_commit(
+        &self,
+        author: &Author,
+        commitid: &str,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Commit, Error>> + Send;
+
+    fn wait_for_commit_with(
+        &self,
+        author: &Author,
+        commitid: &str,
+        options: &WaitOptions,
+    ) -> impl Future<Output = Result<Commit, Error>> + Send;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FakeCommit, FakeServer};
+
+    fn coverage_of(api: &impl CodecovApi, author: &Author, revision: &str) -> Result<f64, Error> {
+        Ok(api.get_totals(author, &revision.parse()?)?.coverage)
+    }
+
+    #[test]
+    fn test_client_as_codecov_api() {
+        let server = FakeServer::start().unwrap();
+        let author: Author = "github/kitsuyui/rust-codecov".parse().unwrap();
+        let totals = Totals {
+            lines: 4,
+            hits: 3,
+            misses: 1,
+            ..Default::default()
+        };
+        let sha = "1eb341765e7c3daa88ae5d2a751538a620c6dbce";
+        server.add_commit(
+            &author,
+            FakeCommit::new(sha, "main", "2023-08-01T15:41:47Z").with_file("src/lib.rs", totals),
+        );
+        let client = server.client();
+        assert_eq!(coverage_of(&client, &author, "main").unwrap(), 75.0);
+        assert_eq!(coverage_of(&client, &author, sha).unwrap(), 75.0);
+        assert!(matches!(
+            coverage_of(&client, &author, "missing"),
+            Err(Error::NotFoundError(_))
+        ));
+        let report = CodecovApi::get_report(&client, &author, &Revision::Branch("main".into()));
+        assert_eq!(report.unwrap().files.len(), 1);
+    }
+}
@@ -0,0 +1,233 @@
+/**
+ * AsyncClient is the async variant of Client, enabled by the `async` feature.
+ * It runs the requests of a blocking Client on the blocking thread pool of the tokio runtime,
+ * so it must be used from within a runtime.
+ *
+ * ```no_run
+ * use codecov::{api::AsyncCodecovApi, async_client::AsyncClient, author::Author, Client};
+ *
+ * # async fn run() -> Result<(), codecov::errors::Error> {
+ * let client = AsyncClient::new(Client::new_from_env()?);
+ * let author: Author = "github/kitsuyui/rust-codecov".parse()?;
+ * let commits = client.get_commits(&author).await?;
+ * # Ok(())
+ * # }
+ * ```
+ */
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::api::{AsyncCodecovApi, CodecovApi};
+use crate::author::Author;
+use crate::branch_detail::{BranchDetailAPIResponse, Report};
+use crate::branches::BranchesAPIResponse;
+use crate::commits::{Commit, CommitHistory, CommitsAPIResponse};
+use crate::errors::Error;
+use crate::gate::{Gate, GateResult};
+use crate::owner::Owner;
+use crate::repos::Repo;
+use crate::revision::Revision;
+use crate::totals::Totals;
+use crate::uploads::Upload;
+use crate::wait::WaitOptions;
+use crate::Client;
+
+#[derive(Clone)]
+pub struct AsyncClient {
+    client: Arc<Client>,
+}
+
+impl AsyncClient {
+    pub fn new(client: Client) -> AsyncClient {
+        AsyncClient {
+            client: Arc::new(client),
+        }
+    }
+
+    fn run<T, F>(&self, f: F) -> impl Future<Output = Result<T, Error>> + Send
+    where
+        T: Send + 'static,
+        F: FnOnce(&Client) -> Result<T, Error> + Send + 'static,
+    {
+        let client = Arc::clone(&self.client);
+        async move {
+            match tokio::task::spawn_blocking(move || f(&client)).await {
+                Ok(result) => result,
+                Err(e) => Err(Error::JoinError(e)),
+            }
+        }
+    }
+}
+
+impl AsyncCodecovApi for AsyncClient {
+    fn get_all_repos(
+        &self,
+        owner: &Owner,
+    ) -> impl Future<Output = Result<Vec<Repo>, Error>> + Send {
+        let owner = owner.clone();
+        self.run(move |client| CodecovApi::get_all_repos(client, &owner))
+    }
+
+    fn get_commits(
+        &self,
+        author: &Author,
+    ) -> impl Future<Output = Result<CommitsAPIResponse, Error>> + Send {
+        let author = author.clone();
+        self.run(move |client| CodecovApi::get_commits(client, &author))
+    }
+
+    fn get_commit_history(
+        &self,
+        author: &Author,
+    ) -> impl Future<Output = Result<CommitHistory, Error>> + Send {
+        let author = author.clone();
+        self.run(move |client| CodecovApi::get_commit_history(client, &author))
+    }
+
+    fn get_commit(
+        &self,
+        author: &Author,
+        commitid: &str,
+    ) -> impl Future<Output = Result<Commit, Error>> + Send {
+        let author = author.clone();
+        let commitid = commitid.to_string();
+        self.run(move |client| CodecovApi::get_commit(client, &author, &commitid))
+    }
+
+    fn get_commit_uploads(
+        &self,
+        author: &Author,
+        commitid: &str,
+    ) -> impl Future<Output = Result<Vec<Upload>, Error>> + Send {
+        let author = author.clone();
+        let commitid = commitid.to_string();
+        self.run(move |client| CodecovApi::get_commit_uploads(client, &author, &commitid))
+    }
+
+    fn get_branches(
+        &self,
+        author: &Author,
+    ) -> impl Future<Output = Result<BranchesAPIResponse, Error>> + Send {
+        let author = author.clone();
+        self.run(move |client| CodecovApi::get_branches(client, &author))
+    }
+
+    fn get_branch_detail(
+        &self,
+        author: &Author,
+        branch_name: &str,
+    ) -> impl Future<Output = Result<BranchDetailAPIResponse, Error>> + Send {
+        let author = author.clone();
+        let branch_name = branch_name.to_string();
+        self.run(move |client| CodecovApi::get_branch_detail(client, &author, &branch_name))
+    }
+
+    fn get_totals(
+        &self,
+        author: &Author,
+        revision: &Revision,
+    ) -> impl Future<Output = Result<Totals, Error>> + Send {
+        let author = author.clone();
+        let revision = revision.clone();
+        self.run(move |client| CodecovApi::get_totals(client, &author, &revision))
+    }
+
+    fn get_report(
+        &self,
+        author: &Author,
+        revision: &Revision,
+    ) -> impl Future<Output = Result<Report, Error>> + Send {
+        let author = author.clone();
+        let revision = revision.clone();
+        self.run(move |client| CodecovApi::get_report(client, &author, &revision))
+    }
+
+    fn check_gate(
+        &self,
+        gate: &Gate,
+        author: &Author,
+        head: &Revision,
+        base: Option<&Revision>,
+    ) -> impl Future<Output = Result<GateResult, Error>> + Send {
+        let gate = gate.clone();
+        let author = author.clone();
+        let head = head.clone();
+        let base = base.cloned();
+        self.run(move |client| CodecovApi::check_gate(client, &gate, &author, &head, base.as_ref()))
+    }
+
+    fn wait_for_commit(
+        &self,
+        author: &Author,
+        commitid: &str,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Commit, Error>> + Send {
+        let author = author.clone();
+        let commitid = commitid.to_string();
+        self.run(move |client| CodecovApi::wait_for_commit(client, &author, &commitid, timeout))
+    }
+
+    /**
+     * Polls on the blocking thread pool, so the backoff does not block the runtime.
+     */
+    fn wait_for_commit_with(
+        &self,
+        author: &Author,
+        commitid: &str,
+        options: &WaitOptions,
+    ) -> impl Future<Output = Result<Commit, Error>> + Send {
+        let author = author.clone();
+        let commitid = commitid.to_string();
+        let options = options.clone();
+        self.run(move |client| {
+            CodecovApi::wait_for_commit_with(client, &author, &commitid, &options)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{FakeCommit, FakeServer};
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_async_client() {
+        let server = FakeServer::start().unwrap();
+        let author: Author = "github/kitsuyui/rust-codecov".parse().unwrap();
+        let sha = "1eb341765e7c3daa88ae5d2a751538a620c6dbce";
+        server.add_commit(
+            &author,
+            FakeCommit::new(sha, "main", "2023-08-01T15:41:47Z"),
+        );
+        let client = AsyncClient::new(server.client());
+        block_on(async {
+            let commits = client.get_commits(&author).await.unwrap();
+            assert_eq!(commits.results[0].commitid, sha);
+            let commit = client.get_commit(&author, sha).await.unwrap();
+            assert_eq!(commit.branch.as_deref(), Some("main"));
+            let branches = client.get_branches(&author).await.unwrap();
+            assert_eq!(branches.results[0].name, "main");
+            assert!(matches!(
+                client.get_commit(&author, "0000000").await,
+                Err(Error::NotFoundError(_))
+            ));
+            let commit = client
+                .wait_for_commit(&author, sha, Duration::from_secs(1))
+                .await
+                .unwrap();
+            assert_eq!(commit.commitid, sha);
+            let gate = Gate::new().with_target(0.0);
+            let head = Revision::Branch("main".to_string());
+            let result = client.check_gate(&gate, &author, &head, None).await;
+            assert!(result.unwrap().passed());
+        });
+    }
+}
@@ -1,31 +1,99 @@
+use std::fmt;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 
+use crate::errors::Error;
+
 use crate::owner::Owner;
+use crate::service::Service;
 
 /**
  * Author is a struct that represents the author of a repo.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Author {
-    pub service: String,
+    pub service: Service,
     pub username: String,
     pub name: String,
 }
 
 impl Author {
-    pub fn new(service: &str, username: &str, name: &str) -> Author {
+    pub fn new(service: Service, username: &str, name: &str) -> Author {
         Author {
-            service: service.to_string(),
+            service,
             username: username.to_string(),
             name: name.to_string(),
         }
     }
 
     pub fn from_owner(owner: &Owner, name: &str) -> Author {
-        Self::new(&owner.service, &owner.username, name)
+        Self::new(owner.service, &owner.username, name)
     }
 
     pub fn to_owner(&self) -> Owner {
-        Owner::new(&self.service, &self.username)
+        Owner::new(self.service, &self.username)
+    }
+
+    /**
+     * Parses a repo slug like "github/kitsuyui/rust-codecov" or "gh/kitsuyui/rust-codecov".
+     * Everything between the service and the repo name is the owner,
+     * so GitLab subgroups like "gitlab/group/subgroup/repo" are owned by "group/subgroup".
+     */
+    pub fn parse(slug: &str) -> Result<Author, Error> {
+        let invalid = || Error::ParseError(format!("expected service/owner/repo: {}", slug));
+        let trimmed = slug.trim_matches('/');
+        let (service, rest) = trimmed.split_once('/').ok_or_else(invalid)?;
+        let (username, name) = rest.rsplit_once('/').ok_or_else(invalid)?;
+        if name.is_empty() || username.split('/').any(str::is_empty) {
+            return Err(invalid());
+        }
+        Ok(Author::new(service.parse()?, username, name))
+    }
+}
+
+/**
+ * RepoRef is an alias of Author, which identifies a repo by service, owner and name.
+ */
+pub type RepoRef = Author;
+
+impl FromStr for Author {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Author::parse(s)
+    }
+}
+
+impl fmt::Display for Author {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.service, self.username, self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let author = RepoRef::parse("gh/kitsuyui/rust-codecov").unwrap();
+        assert_eq!(author.service, Service::Github);
+        assert_eq!(author.username, "kitsuyui");
+        assert_eq!(author.name, "rust-codecov");
+        assert_eq!(author.to_string(), "github/kitsuyui/rust-codecov");
+
+        let author: Author = "gitlab/group/project".parse().unwrap();
+        assert_eq!(author.service, Service::Gitlab);
+        assert!(Author::parse("github/kitsuyui").is_err());
+        assert!(Author::parse("github//rust-codecov").is_err());
+        assert!(Author::parse("svn/kitsuyui/rust-codecov").is_err());
+
+        let author: Author = "gitlab/group/subgroup/repo".parse().unwrap();
+        assert_eq!(author.service, Service::Gitlab);
+        assert_eq!(author.username, "group/subgroup");
+        assert_eq!(author.name, "repo");
+        assert_eq!(author.to_string(), "gitlab/group/subgroup/repo");
+        assert!(Author::parse("gitlab/group//repo").is_err());
     }
 }
@@ -0,0 +1,444 @@
+/**
+ * codecov is a command-line client for the Codecov API (v2).
+ * The token is read from the CODECOV_OWNER_TOKEN environment variable.
+ *
+ * ```sh
+ * codecov repos github/kitsuyui
+ * codecov totals github/kitsuyui/rust-codecov main --json
+ * codecov compare github/kitsuyui/rust-codecov main feature/foo
+ * codecov gate github/kitsuyui/rust-codecov feature/foo --base main --target 80 --max-drop 1
+ * ```
+ */
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+
+use codecov::author::Author;
+use codecov::branch_detail::BranchDetailAPIResponse;
+use codecov::errors::Error;
+use codecov::gate::Gate;
+use codecov::owner::Owner;
+use codecov::revision::Revision;
+use codecov::totals::Totals;
+use codecov::Client;
+
+#[derive(Parser, Debug)]
+#[command(name = "codecov", version, about = "Query the Codecov API (v2)")]
+struct Cli {
+    /// Print JSON instead of a table
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the repos of an owner
+    Repos {
+        /// service/owner, e.g. github/kitsuyui
+        owner: String,
+    },
+    /// List the branches of a repo
+    Branches {
+        /// service/owner/repo, e.g. github/kitsuyui/rust-codecov
+        repo: String,
+    },
+    /// List the commits of a repo
+    Commits {
+        /// service/owner/repo
+        repo: String,
+        /// Follow the pagination and list every commit
+        #[arg(long)]
+        all: bool,
+    },
+    /// Show the head commit of a branch
+    Branch {
+        /// service/owner/repo
+        repo: String,
+        /// Branch name
+        name: String,
+    },
+    /// Compare the totals of two revisions
+    Compare {
+        /// service/owner/repo
+        repo: String,
+        /// Base branch or commit sha ("branch:" and "commit:" prefixes force the kind)
+        base: String,
+        /// Head branch or commit sha
+        head: String,
+    },
+    /// Show the totals of a revision
+    Totals {
+        /// service/owner/repo
+        repo: String,
+        /// Branch or commit sha ("branch:" and "commit:" prefixes force the kind)
+        revision: String,
+    },
+    /// Exit with a non-zero status when coverage does not meet the rules
+    Gate {
+        /// service/owner/repo
+        repo: String,
+        /// Head branch or commit sha
+        head: String,
+        /// Base branch or commit sha, required by --max-drop
+        #[arg(long)]
+        base: Option<String>,
+        /// Minimum coverage of the head in percent
+        #[arg(long)]
+        target: Option<f64>,
+        /// Maximum allowed drop from the base in percentage points
+        #[arg(long)]
+        max_drop: Option<f64>,
+        /// Minimum coverage of the files under a path, as PATH=PERCENT (repeatable)
+        #[arg(long = "path-minimum", value_name = "PATH=PERCENT")]
+        path_minimums: Vec<String>,
+        /// Wait up to SECONDS for Codecov to finish processing the head commit (head must be a sha)
+        #[arg(long, value_name = "SECONDS")]
+        wait: Option<u64>,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: &Cli) -> Result<ExitCode, Error> {
+    let client = Client::new_from_env()?;
+    match &cli.command {
+        Command::Repos { owner } => {
+            let repos = client.get_all_repos(&owner.parse::<Owner>()?)?;
+            let rows = repos
+                .iter()
+                .map(|repo| {
+                    vec![
+                        repo.name.clone(),
+                        repo.language.clone().unwrap_or_default(),
+                        repo.branch.clone().unwrap_or_default(),
+                        repo.active.to_string(),
+                        repo.updatestamp.to_string(),
+                    ]
+                })
+                .collect();
+            output(
+                cli.json,
+                &repos,
+                &["NAME", "LANGUAGE", "BRANCH", "ACTIVE", "UPDATED"],
+                rows,
+            )
+        }
+        Command::Branches { repo } => {
+            let branches = client.get_branches(&repo.parse::<Author>()?)?;
+            let rows = branches
+                .results
+                .iter()
+                .map(|branch| vec![branch.name.clone(), branch.updatestamp.to_string()])
+                .collect();
+            output(cli.json, &branches, &["NAME", "UPDATED"], rows)
+        }
+        Command::Commits { repo, all } => {
+            let author = repo.parse::<Author>()?;
+            let commits = if *all {
+                client.get_commit_history(&author)?.commits
+            } else {
+                client.get_commits(&author)?.results
+            };
+            let rows = commits
+                .iter()
+                .map(|commit| {
+                    vec![
+                        short_sha(&commit.commitid),
+                        commit.timestamp.to_string(),
+                        commit.branch.clone().unwrap_or_default(),
+                        format!("{:.2}", commit.totals.coverage),
+                        commit
+                            .message
+                            .lines()
+                            .next()
+                            .unwrap_or_default()
+                            .to_string(),
+                    ]
+                })
+                .collect();
+            output(
+                cli.json,
+                &commits,
+                &["COMMIT", "TIMESTAMP", "BRANCH", "COVERAGE", "MESSAGE"],
+                rows,
+            )
+        }
+        Command::Branch { repo, name } => {
+            let detail = match client.get_branch_detail(&repo.parse::<Author>()?, name)? {
+                BranchDetailAPIResponse::Success(detail) => detail,
+                BranchDetailAPIResponse::NotFound(not_found) => {
+                    return Err(Error::NotFoundError(not_found.detail))
+                }
+            };
+            let head = &detail.head_commit;
+            let rows = vec![
+                vec!["branch".to_string(), detail.name.clone()],
+                vec!["commit".to_string(), head.commitid.clone()],
+                vec!["state".to_string(), head.state.to_string()],
+                vec!["timestamp".to_string(), head.timestamp.to_string()],
+                vec![
+                    "coverage".to_string(),
+                    format!("{:.2}", head.totals.coverage),
+                ],
+                vec!["files".to_string(), head.totals.files.to_string()],
+                vec!["lines".to_string(), head.totals.lines.to_string()],
+            ];
+            output(cli.json, &detail, &["KEY", "VALUE"], rows)
+        }
+        Command::Compare { repo, base, head } => {
+            let author = repo.parse::<Author>()?;
+            let base = client.get_totals(&author, &base.parse::<Revision>()?)?;
+            let head = client.get_totals(&author, &head.parse::<Revision>()?)?;
+            let comparison = Comparison {
+                coverage_change: head.coverage - base.coverage,
+                base,
+                head,
+            };
+            let rows = compare_rows(&comparison.base, &comparison.head);
+            output(cli.json, &comparison, &["", "BASE", "HEAD", "CHANGE"], rows)
+        }
+        Command::Totals { repo, revision } => {
+            let totals =
+                client.get_totals(&repo.parse::<Author>()?, &revision.parse::<Revision>()?)?;
+            output(cli.json, &totals, &["KEY", "VALUE"], totals_rows(&totals))
+        }
+        Command::Gate {
+            repo,
+            head,
+            base,
+            target,
+            max_drop,
+            path_minimums,
+            wait,
+        } => {
+            let mut gate = Gate::new();
+            if let Some(target) = target {
+                gate = gate.with_target(*target);
+            }
+            if let Some(max_drop) = max_drop {
+                gate = gate.with_max_drop(*max_drop);
+            }
+            for path_minimum in path_minimums {
+                let (path, minimum) = parse_path_minimum(path_minimum)?;
+                gate = gate.with_path_minimum(path, minimum);
+            }
+            let base = match base {
+                Some(base) => Some(base.parse::<Revision>()?),
+                None => None,
+            };
+            let author = repo.parse::<Author>()?;
+            let head = head.parse::<Revision>()?;
+            if let Some(wait) = wait {
+                match &head {
+                    Revision::Commit(commitid) => {
+                        client.wait_for_commit(&author, commitid, Duration::from_secs(*wait))?;
+                    }
+                    Revision::Branch(_) => {
+                        return Err(Error::ParseError(
+                            "--wait needs the head to be a commit sha".to_string(),
+                        ))
+                    }
+                }
+            }
+            let result = client.check_gate(&gate, &author, &head, base.as_ref())?;
+            if cli.json {
+                output(cli.json, &result, &[], vec![])?;
+            } else {
+                println!("{}", result);
+            }
+            if result.passed() {
+                Ok(ExitCode::SUCCESS)
+            } else {
+                Ok(ExitCode::FAILURE)
+            }
+        }
+    }
+}
+
+/**
+ * Parses "src/formats=70" into a path and a minimum coverage.
+ */
+fn parse_path_minimum(s: &str) -> Result<(&str, f64), Error> {
+    match s.rsplit_once('=') {
+        Some((path, minimum)) if !path.is_empty() => match minimum.trim_end_matches('%').parse() {
+            Ok(minimum) => Ok((path, minimum)),
+            Err(_) => Err(Error::ParseError(format!("invalid minimum: {}", s))),
+        },
+        _ => Err(Error::ParseError(format!("expected PATH=PERCENT: {}", s))),
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct Comparison {
+    base: Totals,
+    head: Totals,
+    coverage_change: f64,
+}
+
+/**
+ * Prints value as JSON or the rows as a table.
+ */
+fn output<T: Serialize>(
+    json: bool,
+    value: &T,
+    headers: &[&str],
+    rows: Vec<Vec<String>>,
+) -> Result<ExitCode, Error> {
+    if json {
+        match serde_json::to_string_pretty(value) {
+            Ok(json) => println!("{}", json),
+            Err(e) => return Err(Error::SerializeError(e)),
+        }
+    } else {
+        print!("{}", table(headers, &rows));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/**
+ * Renders rows as left-aligned columns separated by two spaces.
+ */
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            if i < widths.len() {
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+    }
+    let mut out = String::new();
+    let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    for row in std::iter::once(&headers).chain(rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn totals_rows(totals: &Totals) -> Vec<Vec<String>> {
+    let mut rows = vec![vec![
+        "coverage".to_string(),
+        format!("{:.2}", totals.coverage),
+    ]];
+    for (key, value) in counts(totals) {
+        rows.push(vec![key.to_string(), value.to_string()]);
+    }
+    rows
+}
+
+fn compare_rows(base: &Totals, head: &Totals) -> Vec<Vec<String>> {
+    let mut rows = vec![vec![
+        "coverage".to_string(),
+        format!("{:.2}", base.coverage),
+        format!("{:.2}", head.coverage),
+        format!("{:+.2}", head.coverage - base.coverage),
+    ]];
+    for ((key, base), (_, head)) in counts(base).into_iter().zip(counts(head)) {
+        rows.push(vec![
+            key.to_string(),
+            base.to_string(),
+            head.to_string(),
+            format!("{:+}", head as i64 - base as i64),
+        ]);
+    }
+    rows
+}
+
+fn counts(totals: &Totals) -> [(&'static str, usize); 6] {
+    [
+        ("files", totals.files),
+        ("lines", totals.lines),
+        ("hits", totals.hits),
+        ("misses", totals.misses),
+        ("partials", totals.partials),
+        ("branches", totals.branches),
+    ]
+}
+
+fn short_sha(sha: &str) -> String {
+    sha.chars().take(7).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+        let cli = Cli::parse_from([
+            "codecov",
+            "totals",
+            "github/kitsuyui/rust-codecov",
+            "main",
+            "--json",
+        ]);
+        assert!(cli.json);
+        assert!(matches!(cli.command, Command::Totals { .. }));
+    }
+
+    #[test]
+    fn test_parse_path_minimum() {
+        assert_eq!(
+            parse_path_minimum("src/formats=70").unwrap(),
+            ("src/formats", 70.0)
+        );
+        assert_eq!(parse_path_minimum("a=b=75%").unwrap(), ("a=b", 75.0));
+        assert!(parse_path_minimum("src").is_err());
+        assert!(parse_path_minimum("=70").is_err());
+        assert!(parse_path_minimum("src=high").is_err());
+    }
+
+    #[test]
+    fn test_table() {
+        let rows = vec![
+            vec!["main".to_string(), "86.05".to_string()],
+            vec!["feature/foo".to_string(), "".to_string()],
+        ];
+        assert_eq!(
+            table(&["NAME", "COVERAGE"], &rows),
+            "NAME         COVERAGE\nmain         86.05\nfeature/foo\n"
+        );
+    }
+
+    #[test]
+    fn test_compare_rows() {
+        let base = Totals {
+            lines: 10,
+            hits: 8,
+            coverage: 80.0,
+            ..Default::default()
+        };
+        let head = Totals {
+            lines: 12,
+            hits: 9,
+            coverage: 75.0,
+            ..Default::default()
+        };
+        let rows = compare_rows(&base, &head);
+        assert_eq!(rows[0], vec!["coverage", "80.00", "75.00", "-5.00"]);
+        assert_eq!(rows[2], vec!["lines", "10", "12", "+2"]);
+    }
+}
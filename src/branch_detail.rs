@@ -1,11 +1,19 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 
+use crate::commits::CommitState;
+use crate::coverage::{FileCoverage, LineCoverage, LineSession};
+use crate::errors::Error;
+use crate::formats::codecov_json;
+use crate::service::Service;
+use crate::timestamp::Timestamp;
 use crate::totals::Totals;
+use crate::tree::CoverageTree;
 
 /**
  * BranchDetailAPIResponse is an enum wrapping all possible responses from the branches API.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum BranchDetailAPIResponse {
     Success(Box<BranchDetailAPISuccessResponse>),
@@ -15,17 +23,18 @@ pub enum BranchDetailAPIResponse {
 /**
  * BranchesAPIResponse is a struct that represents the response from the branches API.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BranchDetailAPISuccessResponse {
     pub head_commit: HeadCommit,
     pub name: String,
-    pub updatestamp: String, // TODO: ISO Date
+    #[cfg_attr(feature = "chrono", serde(with = "crate::timestamp::serde_timestamp"))]
+    pub updatestamp: Timestamp,
 }
 
 /**
  * BranchNotFound is a struct that represents a branch not found error.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BranchNotFound {
     pub detail: String,
 }
@@ -33,17 +42,18 @@ pub struct BranchNotFound {
 /**
  * Branch is a struct that represents a branch.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct HeadCommit {
     pub author: Author,
     pub branch: String,
     pub ci_passed: Option<bool>,
     pub commitid: String,
     pub message: String,
-    pub parent: String,
+    pub parent: Option<String>,
     pub report: Report,
-    pub state: String,
-    pub timestamp: String, // TODO: ISO Date
+    pub state: CommitState,
+    #[cfg_attr(feature = "chrono", serde(with = "crate::timestamp::serde_timestamp"))]
+    pub timestamp: Timestamp,
     pub totals: Totals,
 }
 
@@ -52,17 +62,17 @@ pub struct HeadCommit {
  * Note: username is an optional field.
  * if the author is a bot, username will be null.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Author {
     pub name: String,
-    pub service: String,
+    pub service: Service,
     pub username: Option<String>,
 }
 
 /**
  * Report is a struct that represents a report.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Report {
     pub files: Vec<File>,
     pub totals: Totals,
@@ -70,11 +80,29 @@ pub struct Report {
 
 /**
  * File is a struct that represents a file.
+ * line_coverage is empty unless the endpoint returns line coverage for the file.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct File {
     pub name: String,
     pub totals: Totals,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub line_coverage: Vec<Line>,
+}
+
+/**
+ * Line is a struct that represents the coverage of a single line of a file.
+ * It is read from either of these shapes:
+ * - `[line, type]` as returned by the report endpoints, where type is 0 (hit), 1 (miss) or 2 (partial).
+ *   Branch counts are unknown in this shape, so partial lines are LineCoverage::PartialUnknown.
+ * - `{"line": 3, "coverage": "1/2", "sessions": [[0, "1/2"], [1, 0]]}` with the coverage encoded
+ *   like Codecov's JSON format and sessions as `[session, coverage, ...]`.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub number: u32,
+    pub coverage: LineCoverage,
+    pub sessions: Vec<LineSession>,
 }
 
 impl BranchDetailAPIResponse {
@@ -98,6 +126,162 @@ impl BranchDetailAPISuccessResponse {
     }
 }
 
+impl Report {
+    /**
+     * Returns the files as a directory tree with totals aggregated per directory.
+     * Fails if a path is both a file and a directory.
+     */
+    pub fn tree(&self) -> Result<CoverageTree, Error> {
+        CoverageTree::from_files(
+            self.files
+                .iter()
+                .map(|file| (file.name.as_str(), &file.totals)),
+        )
+    }
+}
+
+impl File {
+    /**
+     * Returns the lines that were not executed.
+     */
+    pub fn uncovered_lines(&self) -> impl Iterator<Item = &Line> {
+        self.line_coverage
+            .iter()
+            .filter(|line| !line.coverage.is_covered())
+    }
+
+    /**
+     * Returns the lines where only some of the branches were taken.
+     */
+    pub fn partial_lines(&self) -> impl Iterator<Item = &Line> {
+        self.line_coverage
+            .iter()
+            .filter(|line| line.coverage.is_partial())
+    }
+
+    /**
+     * Returns the lines that were executed, including partial lines.
+     */
+    pub fn covered_lines(&self) -> impl Iterator<Item = &Line> {
+        self.line_coverage
+            .iter()
+            .filter(|line| line.coverage.is_covered())
+    }
+
+    /**
+     * Converts the line coverage into a FileCoverage, e.g. to merge it with local reports.
+     */
+    pub fn to_file_coverage(&self) -> FileCoverage {
+        let mut file = FileCoverage::default();
+        for line in &self.line_coverage {
+            file.lines.insert(line.number, line.coverage.clone());
+            if !line.sessions.is_empty() {
+                file.sessions.insert(line.number, line.sessions.clone());
+            }
+        }
+        file.branches = self.totals.branches;
+        file.methods = self.totals.methods;
+        file
+    }
+}
+
+impl<'de> Deserialize<'de> for Line {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        match decode_line(&value) {
+            Ok(line) => Ok(line),
+            Err(message) => Err(serde::de::Error::custom(message)),
+        }
+    }
+}
+
+impl Serialize for Line {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let sessions: Vec<Value> = self
+            .sessions
+            .iter()
+            .map(|session| {
+                Value::from(vec![
+                    Value::from(session.session),
+                    codecov_json::encode_line(&session.coverage),
+                ])
+            })
+            .collect();
+        serde_json::json!({
+            "line": self.number,
+            "coverage": codecov_json::encode_line(&self.coverage),
+            "sessions": sessions,
+        })
+        .serialize(serializer)
+    }
+}
+
+fn decode_line(value: &Value) -> Result<Line, String> {
+    match value {
+        Value::Array(pair) if pair.len() == 2 => {
+            let number = decode_number(&pair[0])?;
+            let coverage = match pair[1].as_u64() {
+                Some(0) => LineCoverage::Hit(1),
+                Some(1) => LineCoverage::Miss,
+                Some(2) => LineCoverage::PartialUnknown,
+                _ => {
+                    return Err(format!(
+                        "invalid coverage type {} at line {}",
+                        pair[1], number
+                    ))
+                }
+            };
+            Ok(Line {
+                number,
+                coverage,
+                sessions: vec![],
+            })
+        }
+        Value::Object(line) => {
+            let number = decode_number(line.get("line").unwrap_or(&Value::Null))?;
+            let coverage = decode_coverage(line.get("coverage").unwrap_or(&Value::Null), number)?;
+            let mut sessions = vec![];
+            if let Some(Value::Array(values)) = line.get("sessions") {
+                for value in values {
+                    let (session, coverage) = match value.as_array().map(Vec::as_slice) {
+                        Some([session, coverage, ..]) => (session, coverage),
+                        _ => return Err(format!("invalid session at line {}", number)),
+                    };
+                    let session = match session.as_u64() {
+                        Some(session) => session as usize,
+                        None => return Err(format!("invalid session id at line {}", number)),
+                    };
+                    sessions.push(LineSession {
+                        session,
+                        coverage: decode_coverage(coverage, number)?,
+                    });
+                }
+            }
+            Ok(Line {
+                number,
+                coverage,
+                sessions,
+            })
+        }
+        _ => Err(format!("invalid line coverage {}", value)),
+    }
+}
+
+fn decode_number(value: &Value) -> Result<u32, String> {
+    match value.as_u64().map(u32::try_from) {
+        Some(Ok(number)) => Ok(number),
+        _ => Err(format!("invalid line number {}", value)),
+    }
+}
+
+fn decode_coverage(value: &Value, number: u32) -> Result<LineCoverage, String> {
+    match codecov_json::decode_line(value) {
+        Ok(Some((coverage, _))) => Ok(coverage),
+        Ok(None) => Err(format!("missing coverage at line {}", number)),
+        Err(message) => Err(format!("{} at line {}", message, number)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -202,6 +386,87 @@ mod tests {
                 "sessions": 1
             }
         });
-        serde_json::from_value::<Report>(j).unwrap();
+        let report = serde_json::from_value::<Report>(j).unwrap();
+        let tree = report.tree().unwrap();
+        assert_eq!(tree.get("src").unwrap().totals.misses, 12);
+        assert_eq!(tree.get("src").unwrap().totals.files, 1);
+    }
+
+    #[test]
+    fn test_deserialize_line_coverage() {
+        let j = json!({
+            "name": "src/lib.rs",
+            "totals": {"lines": 4, "hits": 2, "misses": 1, "partials": 1, "coverage": 50.0},
+            "line_coverage": [[1, 0], [2, 1], [3, 2], [5, 0]]
+        });
+        let file = serde_json::from_value::<File>(j).unwrap();
+        let uncovered: Vec<u32> = file.uncovered_lines().map(|line| line.number).collect();
+        let partial: Vec<u32> = file.partial_lines().map(|line| line.number).collect();
+        assert_eq!(uncovered, vec![2]);
+        assert_eq!(partial, vec![3]);
+        assert_eq!(file.covered_lines().count(), 3);
+        assert_eq!(file.to_file_coverage().totals().lines, 4);
+
+        let j = json!({
+            "name": "src/lib.rs",
+            "totals": {},
+            "line_coverage": [
+                {"line": 3, "coverage": "1/2", "sessions": [[0, "1/2"], [1, 0]]},
+                {"line": 4, "coverage": 2, "sessions": [[0, 2, null, null, null]]}
+            ]
+        });
+        let file = serde_json::from_value::<File>(j).unwrap();
+        let line = &file.line_coverage[0];
+        assert_eq!(
+            line.coverage,
+            LineCoverage::Partial {
+                covered: 1,
+                total: 2
+            }
+        );
+        assert_eq!(line.sessions[1].session, 1);
+        assert_eq!(line.sessions[1].coverage, LineCoverage::Miss);
+        assert_eq!(file.line_coverage[1].coverage, LineCoverage::Hit(2));
+
+        let round_trip = serde_json::to_value(&file).unwrap();
+        let file = serde_json::from_value::<File>(round_trip).unwrap();
+        assert_eq!(file.line_coverage[0].sessions.len(), 2);
+
+        let j = json!({"name": "a", "totals": {}, "line_coverage": [[1, 7]]});
+        assert!(serde_json::from_value::<File>(j).is_err());
+    }
+
+    #[test]
+    fn test_partial_line_without_branch_counts() {
+        let j = json!({"name": "src/lib.rs", "totals": {}, "line_coverage": [[1, 2], [2, 1]]});
+        let file = serde_json::from_value::<File>(j)
+            .unwrap()
+            .to_file_coverage();
+        assert_eq!(file.lines[&1], LineCoverage::PartialUnknown);
+
+        let mut report = crate::coverage::CoverageReport::default();
+        report.files.insert("src/lib.rs".to_string(), file.clone());
+        let json = codecov_json::to_string(&report).unwrap();
+        let read = codecov_json::parse(&json).unwrap();
+        assert_eq!(read.files["src/lib.rs"].totals().partials, 1);
+        assert_eq!(read.files["src/lib.rs"].totals().misses, 1);
+
+        let strategy = crate::merge::MergeStrategy::Sum;
+        let merged = crate::merge::merge_line(&file.lines[&1], &file.lines[&1], strategy);
+        assert_eq!(merged, LineCoverage::PartialUnknown);
+        let merged = crate::merge::merge_line(&file.lines[&1], &file.lines[&2], strategy);
+        assert_eq!(merged, LineCoverage::PartialUnknown);
+        let merged = crate::merge::merge_line(
+            &file.lines[&1],
+            &read.files["src/lib.rs"].lines[&1],
+            strategy,
+        );
+        assert_eq!(
+            merged,
+            LineCoverage::Partial {
+                covered: 1,
+                total: 2
+            }
+        );
     }
 }
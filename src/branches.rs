@@ -1,11 +1,12 @@
 use serde::{Deserialize, Serialize};
 
+use crate::timestamp::Timestamp;
 use crate::url::Url;
 
 /**
  * BranchesAPIResponse is a struct that represents the response from the branches API.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BranchesAPIResponse {
     pub results: Vec<Branch>,
     pub count: usize,
@@ -17,8 +18,9 @@ pub struct BranchesAPIResponse {
 /**
  * Branch is a struct that represents a branch.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Branch {
     pub name: String,
-    pub updatestamp: String, // TODO: ISO Date
+    #[cfg_attr(feature = "chrono", serde(with = "crate::timestamp::serde_timestamp"))]
+    pub updatestamp: Timestamp,
 }
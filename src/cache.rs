@@ -0,0 +1,408 @@
+/**
+ * On-disk cache of API responses for Client::with_cache.
+ *
+ * Responses are stored per token (a SHA-256 digest of it, never the token itself) and URL.
+ * On unix, directories are created as 0700 and files as 0600 since responses may be private.
+ * A response younger than the TTL of its endpoint is served from disk without a request.
+ * An older one is revalidated with If-None-Match / If-Modified-Since,
+ * and served from disk again when the API answers 304 Not Modified.
+ *
+ * ```no_run
+ * use std::time::Duration;
+ * use codecov::{cache::{Cache, CacheMode}, endpoint::Endpoint, Client};
+ *
+ * let cache = Cache::new(std::path::Path::new(".codecov-cache"))
+ *     .with_default_ttl(Duration::from_secs(60))
+ *     .with_ttl(Endpoint::Repos, Duration::from_secs(3600));
+ * let client = Client::new_from_env().unwrap().with_cache(cache);
+ * // A single call can skip the cache on a clone of the client.
+ * let fresh = client.clone().with_cache_mode(CacheMode::Bypass);
+ * ```
+ */
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::endpoint::Endpoint;
+use crate::errors::Error;
+use crate::url::Url;
+use crate::Client;
+
+/**
+ * Cache is a directory of cached responses with a TTL per endpoint.
+ * The default TTL is zero, i.e. every response is revalidated before it is used.
+ */
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    default_ttl: Duration,
+    ttls: BTreeMap<Endpoint, Duration>,
+}
+
+/**
+ * CacheMode decides how a Client uses its cache.
+ * Use serves fresh responses and revalidates stale ones,
+ * Revalidate revalidates every response regardless of the TTL,
+ * Bypass neither reads nor writes the cache.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheMode {
+    #[default]
+    Use,
+    Revalidate,
+    Bypass,
+}
+
+/**
+ * Validators are the response headers used for conditional requests.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub(crate) struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct CacheEntry {
+    pub url: String,
+    pub validators: Validators,
+    /**
+     * stored_at is the time the response was stored or last revalidated, in seconds since the epoch.
+     */
+    pub stored_at: u64,
+    pub body: Value,
+}
+
+impl Cache {
+    pub fn new(dir: &Path) -> Cache {
+        Cache {
+            dir: dir.to_path_buf(),
+            default_ttl: Duration::ZERO,
+            ttls: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_default_ttl(mut self, ttl: Duration) -> Cache {
+        self.default_ttl = ttl;
+        self
+    }
+
+    pub fn with_ttl(mut self, endpoint: Endpoint, ttl: Duration) -> Cache {
+        self.ttls.insert(endpoint, ttl);
+        self
+    }
+
+    pub fn ttl(&self, endpoint: Endpoint) -> Duration {
+        match self.ttls.get(&endpoint) {
+            Some(ttl) => *ttl,
+            None => self.default_ttl,
+        }
+    }
+
+    /**
+     * Removes every cached response, for all tokens.
+     */
+    pub fn clear(&self) -> Result<(), Error> {
+        remove_dir(&self.dir)
+    }
+
+    fn scope_dir(&self, token: &str) -> PathBuf {
+        let digest = Sha256::digest(token.as_bytes());
+        let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        self.dir.join(hex)
+    }
+
+    fn entry_path(&self, token: &str, url: &Url) -> PathBuf {
+        self.scope_dir(token)
+            .join(format!("{:016x}.json", fnv1a(url.as_str().as_bytes())))
+    }
+
+    /**
+     * Returns the cached response for url, if any.
+     * Unreadable entries are treated as missing, so a corrupted cache only costs a request.
+     */
+    pub(crate) fn load(&self, token: &str, url: &Url) -> Option<CacheEntry> {
+        let json = std::fs::read_to_string(self.entry_path(token, url)).ok()?;
+        let entry = serde_json::from_str::<CacheEntry>(&json).ok()?;
+        if entry.url == url.as_str() {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn store(&self, token: &str, entry: &CacheEntry) -> Result<(), Error> {
+        let url = match Url::parse(&entry.url) {
+            Ok(url) => url,
+            Err(e) => return Err(Error::UrlError(e)),
+        };
+        let path = self.entry_path(token, &url);
+        let json = match serde_json::to_string(entry) {
+            Ok(json) => json,
+            Err(e) => return Err(Error::SerializeError(e)),
+        };
+        if let Err(e) = create_private_dir(&self.scope_dir(token)) {
+            return Err(Error::IoError(e));
+        }
+        match write_private_file(&path, json.as_bytes()) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Error::IoError(e)),
+        }
+    }
+}
+
+impl CacheEntry {
+    fn is_fresh(&self, ttl: Duration, now: u64) -> bool {
+        now.saturating_sub(self.stored_at) < ttl.as_secs()
+    }
+}
+
+impl Client {
+    /**
+     * Returns the client that caches responses in cache.
+     * Only the HTTP transport is cached; recording and replaying always see every request.
+     */
+    pub fn with_cache(mut self, cache: Cache) -> Client {
+        self.cache = Some(cache);
+        self
+    }
+
+    /**
+     * Returns the client with another CacheMode. Defaults to CacheMode::Use.
+     */
+    pub fn with_cache_mode(mut self, mode: CacheMode) -> Client {
+        self.cache_mode = mode;
+        self
+    }
+
+    /**
+     * Removes the cached responses of this client's token.
+     */
+    pub fn clear_cache(&self) -> Result<(), Error> {
+        match &self.cache {
+            Some(cache) => remove_dir(&cache.scope_dir(&self.token)),
+            None => Ok(()),
+        }
+    }
+
+    /**
+     * Sends a GET request through the cache and returns the status and the body.
+     * Only successful responses are stored; a revalidated entry is returned as 200.
+     */
+    pub(crate) fn cached_get(&self, cache: &Cache, url: &Url) -> Result<(u16, Value), Error> {
+        let now = now();
+        let entry = cache.load(&self.token, url);
+        if let Some(entry) = &entry {
+            let ttl = cache.ttl(self.endpoint(url));
+            if self.cache_mode == CacheMode::Use && entry.is_fresh(ttl, now) {
+                return Ok((200, entry.body.clone()));
+            }
+        }
+        let (status, validators, body) =
+            self.http_get(url, entry.as_ref().map(|entry| &entry.validators))?;
+        let entry = match (status, entry) {
+            (304, Some(entry)) => CacheEntry {
+                stored_at: now,
+                ..entry
+            },
+            (200, _) => CacheEntry {
+                url: url.to_string(),
+                validators,
+                stored_at: now,
+                body,
+            },
+            _ => return Ok((status, body)),
+        };
+        cache.store(&self.token, &entry)?;
+        Ok((200, entry.body))
+    }
+}
+
+fn now() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs(),
+        Err(_) => 0,
+    }
+}
+
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+/**
+ * Writes a file through a temporary file in the same directory and renames it into place,
+ * so readers never see a partially written entry.
+ */
+fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let tmp = path.with_extension(format!(
+        "{}-{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let written = options
+        .open(&tmp)
+        .and_then(|mut file| file.write_all(contents))
+        .and_then(|()| std::fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    written
+}
+
+fn remove_dir(dir: &Path) -> Result<(), Error> {
+    match std::fs::remove_dir_all(dir) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(Error::IoError(e)),
+    }
+}
+
+/**
+ * 64-bit FNV-1a hash. Cache file names must be stable across builds, which std's hashers are not.
+ */
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::author::Author;
+    use crate::testing::{FakeCommit, FakeServer};
+
+    fn cache_dir(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("codecov-cache-{}", std::process::id()))
+            .join(name)
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn test_store() {
+        let dir = cache_dir("store");
+        let cache = Cache::new(&dir);
+        let url = Url::parse("https://codecov.io/api/v2/github/kitsuyui/repos").unwrap();
+        let entry = CacheEntry {
+            url: url.to_string(),
+            validators: Validators::default(),
+            stored_at: 100,
+            body: Value::from(1),
+        };
+        cache.store("token", &entry).unwrap();
+        cache.store("token", &entry).unwrap();
+        assert_eq!(cache.load("token", &url).unwrap().body, Value::from(1));
+
+        let scope = cache.scope_dir("token");
+        assert_eq!(
+            scope.file_name().unwrap(),
+            "3c469e9d6c5875d37a43f353d4f88e61fcf812c66eee3457465a40b0da4153e0"
+        );
+        let files: Vec<_> = std::fs::read_dir(&scope).unwrap().collect();
+        assert_eq!(files.len(), 1);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&scope), 0o700);
+            assert_eq!(mode(&cache.entry_path("token", &url)), 0o600);
+        }
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn test_ttl() {
+        let cache = Cache::new(Path::new("unused"))
+            .with_default_ttl(Duration::from_secs(60))
+            .with_ttl(Endpoint::Repos, Duration::from_secs(3600));
+        assert_eq!(cache.ttl(Endpoint::Repos), Duration::from_secs(3600));
+        assert_eq!(cache.ttl(Endpoint::Commit), Duration::from_secs(60));
+        let entry = CacheEntry {
+            url: "https://codecov.io/api/v2".to_string(),
+            validators: Validators::default(),
+            stored_at: 100,
+            body: Value::Null,
+        };
+        assert!(entry.is_fresh(Duration::from_secs(60), 159));
+        assert!(!entry.is_fresh(Duration::from_secs(60), 160));
+        assert!(!entry.is_fresh(Duration::ZERO, 100));
+    }
+
+    #[test]
+    fn test_cached_get() {
+        let dir = cache_dir("cached_get");
+        let server = FakeServer::start().unwrap();
+        let author: Author = "github/kitsuyui/rust-codecov".parse().unwrap();
+        server.add_commit(
+            &author,
+            FakeCommit::new(&format!("{:040x}", 1), "main", "2023-08-01T15:41:47Z"),
+        );
+
+        // Fresh responses are served without a request.
+        let client = server
+            .client()
+            .with_cache(Cache::new(&dir).with_ttl(Endpoint::Branches, Duration::from_secs(3600)));
+        assert_eq!(client.get_branches(&author).unwrap().count, 1);
+        assert_eq!(client.get_branches(&author).unwrap().count, 1);
+        assert_eq!(server.statuses(), vec![200]);
+
+        // Stale responses are revalidated.
+        assert_eq!(client.get_commits(&author).unwrap().count, 1);
+        assert_eq!(client.get_commits(&author).unwrap().count, 1);
+        assert_eq!(server.statuses(), vec![200, 200, 304]);
+
+        // Changed responses replace the cached one.
+        server.add_commit(
+            &author,
+            FakeCommit::new(&format!("{:040x}", 2), "main", "2023-08-02T15:41:47Z"),
+        );
+        assert_eq!(client.get_commits(&author).unwrap().count, 2);
+        assert_eq!(client.get_branches(&author).unwrap().count, 1);
+        let revalidating = client.clone().with_cache_mode(CacheMode::Revalidate);
+        assert_eq!(revalidating.get_branches(&author).unwrap().count, 1);
+        assert_eq!(server.statuses(), vec![200, 200, 304, 200, 200]);
+
+        // Bypass sends plain requests, and clearing empties the cache.
+        let bypassing = client.clone().with_cache_mode(CacheMode::Bypass);
+        bypassing.get_commits(&author).unwrap();
+        bypassing.get_commits(&author).unwrap();
+        client.clear_cache().unwrap();
+        assert_eq!(client.get_branches(&author).unwrap().count, 1);
+        assert_eq!(
+            server.statuses(),
+            vec![200, 200, 304, 200, 200, 200, 200, 200]
+        );
+
+        // Other tokens have their own entries.
+        server.set_token("another-token");
+        let other = Client::new("another-token".to_string())
+            .with_api_endpoint(&server.api_endpoint())
+            .with_cache(Cache::new(&dir).with_default_ttl(Duration::from_secs(3600)));
+        other.get_branches(&author).unwrap();
+        assert_eq!(server.statuses().len(), 9);
+
+        Cache::new(&dir).clear().unwrap();
+        assert!(!dir.exists());
+    }
+}
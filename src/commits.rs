@@ -2,14 +2,25 @@
  * Codecov v2 API
  * /repos endpoint returns a list of repos for a given owner.
  */
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
+use crate::branch_detail::Report;
+use crate::service::Service;
+use crate::timestamp::{self, Timestamp};
 use crate::url::Url;
 
+/**
+ * Totals used to be a separate struct with `diff: usize`, which failed on real payloads.
+ * It is kept as a re-export of the canonical crate::totals::Totals.
+ */
+pub use crate::totals::Totals;
+
 /**
  * CommitsAPIResponse is a struct that represents the response from the commits API.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CommitsAPIResponse {
     pub results: Vec<Commit>,
     pub count: usize,
@@ -23,9 +34,9 @@ pub struct CommitsAPIResponse {
  * Note: This is different from the Author struct in src/author.rs.
  * name is optional in this struct.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CommitAuthor {
-    pub service: String,
+    pub service: Service,
     pub username: String,
     pub name: Option<String>,
 }
@@ -33,40 +44,78 @@ pub struct CommitAuthor {
 /**
  * Commit is a struct that represents a commit.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Commit {
     pub commitid: String,
     pub message: String,
-    pub timestamp: String, // TODO: ISO Date
+    #[cfg_attr(feature = "chrono", serde(with = "crate::timestamp::serde_timestamp"))]
+    pub timestamp: Timestamp,
     pub ci_passed: bool,
     pub author: CommitAuthor,
     pub branch: Option<String>,
     pub totals: Totals,
-    pub state: String,
+    pub state: CommitState,
     pub parent: Option<String>,
+    /**
+     * report is only returned by the single commit endpoint.
+     */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<Report>,
+}
+
+/**
+ * CommitHistory is a list of commits gathered from all pages of the commits API, oldest first.
+ * Aggregations only consider commits with a report, i.e. totals.lines > 0.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CommitHistory {
+    pub commits: Vec<Commit>,
 }
 
 /**
- * Totals is a struct that represents the totals for a commit.
+ * CommitState is the processing state of a commit on Codecov.
+ * States this crate does not know yet are read as Unknown.
  */
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Totals {
-    pub files: usize,
-    pub lines: usize,
-    pub hits: usize,
-    pub misses: usize,
-    pub partials: usize,
-    pub coverage: f64,
-    pub branches: usize,
-    pub methods: usize,
-    pub sessions: usize,
-    pub complexity: f64,
-    pub complexity_total: f64,
-    pub complexity_ratio: f64,
-    pub diff: usize,
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitState {
+    Pending,
+    Complete,
+    Error,
+    Skipped,
+    #[serde(other)]
+    Unknown,
+}
+
+impl CommitState {
+    /**
+     * Returns true if Codecov has finished processing the commit, successfully or not.
+     */
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            CommitState::Complete | CommitState::Error | CommitState::Skipped
+        )
+    }
+}
+
+impl fmt::Display for CommitState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = match self {
+            CommitState::Pending => "pending",
+            CommitState::Complete => "complete",
+            CommitState::Error => "error",
+            CommitState::Skipped => "skipped",
+            CommitState::Unknown => "unknown",
+        };
+        f.write_str(state)
+    }
 }
 
 impl CommitsAPIResponse {
+    #[deprecated(
+        note = "divides a single page by the total count and is not weighted; use CommitHistory instead"
+    )]
     pub fn coverage(&self) -> Option<f64> {
         if self.count == 0 {
             return None;
@@ -77,13 +126,121 @@ impl CommitsAPIResponse {
         }
         Some(total_coverage / self.count as f64)
     }
+
+    /**
+     * Returns the commits whose timestamp is within from..=to.
+     */
+    pub fn commits_between(&self, from: &Timestamp, to: &Timestamp) -> Vec<&Commit> {
+        self.results
+            .iter()
+            .filter(|commit| timestamp::in_range(&commit.timestamp, from, to))
+            .collect()
+    }
+
+    /**
+     * Sorts the commits by timestamp, oldest first.
+     */
+    pub fn sort_by_timestamp(&mut self) {
+        sort_by_timestamp(&mut self.results);
+    }
+}
+
+/**
+ * Sorts commits by timestamp, oldest first.
+ */
+// sort_by_key would need to clone the timestamp when it is a String.
+#[allow(clippy::unnecessary_sort_by)]
+fn sort_by_timestamp(commits: &mut [Commit]) {
+    commits.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+}
+
+impl CommitHistory {
+    /**
+     * Returns a history of the given commits sorted by timestamp.
+     */
+    pub fn new(mut commits: Vec<Commit>) -> CommitHistory {
+        sort_by_timestamp(&mut commits);
+        CommitHistory { commits }
+    }
+
+    fn reported(&self) -> impl DoubleEndedIterator<Item = &Commit> {
+        self.commits.iter().filter(|commit| commit.totals.lines > 0)
+    }
+
+    /**
+     * Returns the mean coverage weighted by the number of lines of each commit.
+     */
+    pub fn weighted_mean(&self) -> Option<f64> {
+        let mut lines = 0.0;
+        let mut weighted = 0.0;
+        for commit in self.reported() {
+            lines += commit.totals.lines as f64;
+            weighted += commit.totals.coverage * commit.totals.lines as f64;
+        }
+        if lines == 0.0 {
+            return None;
+        }
+        Some(weighted / lines)
+    }
+
+    /**
+     * Returns the median coverage. For an even number of commits, the mean of the two middle values.
+     */
+    pub fn median(&self) -> Option<f64> {
+        let mut coverages: Vec<f64> = self.reported().map(|c| c.totals.coverage).collect();
+        if coverages.is_empty() {
+            return None;
+        }
+        coverages.sort_by(f64::total_cmp);
+        let middle = coverages.len() / 2;
+        if coverages.len() % 2 == 1 {
+            Some(coverages[middle])
+        } else {
+            Some((coverages[middle - 1] + coverages[middle]) / 2.0)
+        }
+    }
+
+    /**
+     * Returns the commit with the lowest coverage.
+     */
+    pub fn min(&self) -> Option<&Commit> {
+        self.reported()
+            .min_by(|a, b| a.totals.coverage.total_cmp(&b.totals.coverage))
+    }
+
+    /**
+     * Returns the commit with the highest coverage.
+     */
+    pub fn max(&self) -> Option<&Commit> {
+        self.reported()
+            .max_by(|a, b| a.totals.coverage.total_cmp(&b.totals.coverage))
+    }
+
+    /**
+     * Returns the most recent commit.
+     */
+    pub fn latest(&self) -> Option<&Commit> {
+        self.reported().next_back()
+    }
+
+    /**
+     * Returns the coverage as of the given time, i.e. of the latest commit at or before it.
+     */
+    pub fn coverage_at(&self, at: &Timestamp) -> Option<f64> {
+        self.reported()
+            .rev()
+            .find(|commit| &commit.timestamp <= at)
+            .map(|commit| commit.totals.coverage)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     #[test]
+    #[allow(deprecated)]
     fn test_coverage() {
         use super::*;
+        use crate::totals::Diff;
         let mut response = CommitsAPIResponse {
             results: vec![],
             count: 1,
@@ -95,10 +252,10 @@ mod tests {
         let commit = Commit {
             commitid: String::from("123"),
             message: String::from("message"),
-            timestamp: String::from("timestamp"),
+            timestamp: "2023-08-01T15:41:47Z".parse().unwrap(),
             ci_passed: true,
             author: CommitAuthor {
-                service: String::from("service"),
+                service: Service::Github,
                 username: String::from("username"),
                 name: Some(String::from("name")),
             },
@@ -116,12 +273,177 @@ mod tests {
                 complexity: 1.0,
                 complexity_total: 1.0,
                 complexity_ratio: 1.0,
-                diff: 1,
+                messages: 0,
+                diff: Diff::Value(1),
             },
-            state: String::from("state"),
+            state: CommitState::Complete,
             parent: Some(String::from("parent")),
+            report: None,
         };
         response.results.push(commit);
         assert_eq!(response.coverage(), Some(2.0));
     }
+
+    #[test]
+    fn test_deserialize_commit_with_diff_array() {
+        use super::*;
+        let j = serde_json::json!({
+            "commitid": "1eb341765e7c3daa88ae5d2a751538a620c6dbce",
+            "message": "fix",
+            "timestamp": "2023-08-01T15:41:47Z",
+            "ci_passed": true,
+            "author": {"service": "github", "username": "kitsuyui", "name": null},
+            "branch": "main",
+            "totals": {
+                "files": 10,
+                "lines": 172,
+                "hits": 148,
+                "misses": 23,
+                "partials": 1,
+                "coverage": 86.05,
+                "branches": 22,
+                "methods": 0,
+                "sessions": 1,
+                "complexity": null,
+                "complexity_total": null,
+                "complexity_ratio": 0,
+                "diff": [1, 2, 1, 1, 0, "50.00000", 0, 0, 0, 0, null, null, 0]
+            },
+            "state": "complete",
+            "parent": null
+        });
+        let commit = serde_json::from_value::<Commit>(j).unwrap();
+        assert_eq!(commit.totals.diff.get_f64(5), Some(50.0));
+        let patch = commit.totals.patch().unwrap().unwrap();
+        assert_eq!(patch.lines, 2);
+        assert_eq!(patch.hits, 1);
+        assert_eq!(patch.misses, 1);
+        assert_eq!(patch.partials, 0);
+        assert_eq!(patch.coverage, Some(50.0));
+        assert_eq!(commit.state, CommitState::Complete);
+    }
+
+    #[test]
+    fn test_commit_state() {
+        use super::*;
+        let state = serde_json::from_str::<CommitState>("\"pending\"").unwrap();
+        assert_eq!(state, CommitState::Pending);
+        assert!(!state.is_finished());
+        let state = serde_json::from_str::<CommitState>("\"processing\"").unwrap();
+        assert_eq!(state, CommitState::Unknown);
+        assert!(CommitState::Error.is_finished());
+        assert_eq!(CommitState::Skipped.to_string(), "skipped");
+    }
+
+    #[test]
+    fn test_commits_between() {
+        use super::*;
+        use crate::totals::Diff;
+        let commit = |commitid: &str, timestamp: &str| Commit {
+            commitid: commitid.to_string(),
+            message: String::from("message"),
+            timestamp: timestamp.parse().unwrap(),
+            ci_passed: true,
+            author: CommitAuthor {
+                service: Service::Github,
+                username: String::from("username"),
+                name: None,
+            },
+            branch: None,
+            totals: Totals {
+                files: 1,
+                lines: 1,
+                hits: 1,
+                misses: 0,
+                partials: 0,
+                coverage: 100.0,
+                branches: 0,
+                methods: 0,
+                sessions: 1,
+                complexity: 0.0,
+                complexity_total: 0.0,
+                complexity_ratio: 0.0,
+                messages: 0,
+                diff: Diff::Value(0),
+            },
+            state: CommitState::Complete,
+            parent: None,
+            report: None,
+        };
+        let mut response = CommitsAPIResponse {
+            results: vec![
+                commit("c", "2023-08-03T00:00:00Z"),
+                commit("a", "2023-08-01T00:00:00Z"),
+                commit("b", "2023-08-02T00:00:00Z"),
+            ],
+            count: 3,
+            next: None,
+            previous: None,
+            total_pages: 1,
+        };
+        response.sort_by_timestamp();
+        let ids: Vec<&str> = response
+            .results
+            .iter()
+            .map(|c| c.commitid.as_str())
+            .collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        let from = "2023-08-02T00:00:00Z".parse().unwrap();
+        let to = "2023-08-03T00:00:00Z".parse().unwrap();
+        assert_eq!(response.commits_between(&from, &to).len(), 2);
+
+        #[cfg(feature = "chrono")]
+        {
+            let to = response.results[2].timestamp;
+            let from = to - chrono::Duration::days(1);
+            assert_eq!(response.commits_between(&from, &to).len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_commit_history() {
+        use super::*;
+        let commit = |timestamp: &str, lines: usize, coverage: f64| Commit {
+            commitid: timestamp.to_string(),
+            message: String::from("message"),
+            timestamp: timestamp.parse().unwrap(),
+            ci_passed: true,
+            author: CommitAuthor {
+                service: Service::Github,
+                username: String::from("username"),
+                name: None,
+            },
+            branch: None,
+            totals: Totals {
+                lines,
+                coverage,
+                ..Default::default()
+            },
+            state: CommitState::Complete,
+            parent: None,
+            report: None,
+        };
+        let history = CommitHistory::new(vec![
+            commit("2023-08-03T00:00:00Z", 300, 90.0),
+            commit("2023-08-01T00:00:00Z", 100, 50.0),
+            commit("2023-08-04T00:00:00Z", 0, 0.0),
+            commit("2023-08-02T00:00:00Z", 100, 70.0),
+        ]);
+        assert_eq!(history.weighted_mean(), Some(78.0));
+        assert_eq!(history.median(), Some(70.0));
+        assert_eq!(history.min().unwrap().totals.coverage, 50.0);
+        assert_eq!(history.max().unwrap().totals.coverage, 90.0);
+        assert_eq!(history.latest().unwrap().commitid, "2023-08-03T00:00:00Z");
+        let at = "2023-08-02T12:00:00Z".parse().unwrap();
+        assert_eq!(history.coverage_at(&at), Some(70.0));
+        let before = "2023-07-01T00:00:00Z".parse().unwrap();
+        assert_eq!(history.coverage_at(&before), None);
+
+        let history = CommitHistory::new(vec![
+            commit("2023-08-01T00:00:00Z", 10, 40.0),
+            commit("2023-08-02T00:00:00Z", 10, 60.0),
+        ]);
+        assert_eq!(history.median(), Some(50.0));
+        assert_eq!(CommitHistory::default().weighted_mean(), None);
+    }
 }
@@ -0,0 +1,1170 @@
+/**
+ * Typed representation of `codecov.yml`.
+ * https://docs.codecov.com/docs/codecov-yaml
+ */
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::errors::Error;
+use crate::path_fixer::PathFixer;
+
+/**
+ * CodecovConfig is a struct that represents the whole codecov.yml.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct CodecovConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codecov: Option<CodecovSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageSettings>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub flags: BTreeMap<String, FlagConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag_management: Option<FlagManagement>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component_management: Option<ComponentManagement>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<Toggle<CommentConfig>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github_checks: Option<Toggle<GithubChecks>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slack_app: Option<Toggle<serde_yaml::Value>>,
+    /**
+     * Sections that are passed through without a typed schema.
+     */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parsers: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cli: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profiling: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_analysis: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_analytics: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_pr_review: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub beta_groups: Option<serde_yaml::Value>,
+}
+
+/**
+ * CodecovSettings is a struct that represents the top level `codecov:` section.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct CodecovSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub require_ci_to_pass: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub disable_default_path_fixes: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_report_age: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict_yaml_branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bot: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ci: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify: Option<NotifySettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<ArchiveSettings>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub allow_coverage_offsets: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub allow_pseudo_compare: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/**
+ * ArchiveSettings is a struct that represents `codecov.archive`.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct ArchiveSettings {
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub uploads: Option<bool>,
+}
+
+/**
+ * NotifySettings is a struct that represents `codecov.notify`.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct NotifySettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_n_builds: Option<u32>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub wait_for_ci: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub manual_trigger: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub notify_error: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub countdown: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay: Option<u32>,
+}
+
+/**
+ * CoverageSettings is a struct that represents the `coverage:` section.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct CoverageSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precision: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub round: Option<Round>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<Range>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Toggle<StatusSettings>>,
+    /**
+     * notify holds the legacy notifications (slack, gitter, email, webhook, irc, hipchat).
+     */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify: Option<serde_yaml::Value>,
+}
+
+/**
+ * Round is an enum that represents `coverage.round`.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Round {
+    Down,
+    Up,
+    Nearest,
+}
+
+/**
+ * StatusSettings is a struct that represents `coverage.status`.
+ * Each kind of status is either disabled/enabled or a map of named statuses.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct StatusSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<Toggle<BTreeMap<String, StatusConfig>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<Toggle<BTreeMap<String, StatusConfig>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<Toggle<BTreeMap<String, StatusConfig>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_rules: Option<StatusDefaultRules>,
+}
+
+/**
+ * StatusDefaultRules is a struct that represents `coverage.status.default_rules`.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct StatusDefaultRules {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag_coverage_not_uploaded_behavior: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carryforward_behavior: Option<String>,
+}
+
+/**
+ * StatusConfig is a struct that represents a single named status like `coverage.status.project.default`.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct StatusConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<Target>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<Percentage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<String>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub informational: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub only_pulls: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_ci_failed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_not_found: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed_code_behavior: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carryforward_behavior: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag_coverage_not_uploaded_behavior: Option<String>,
+}
+
+/**
+ * FlagConfig is a struct that represents an entry of `flags:`.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct FlagConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub carryforward: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carryforward_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_n_builds: Option<u32>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub joined: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+}
+
+/**
+ * FlagManagement is a struct that represents `flag_management:`.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct FlagManagement {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_rules: Option<FlagRule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub individual_flags: Vec<FlagRule>,
+}
+
+/**
+ * FlagRule is a struct that represents the default rules or an individual flag of `flag_management:`.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct FlagRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub carryforward: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carryforward_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_n_builds: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statuses: Vec<RuleStatus>,
+}
+
+/**
+ * ComponentManagement is a struct that represents `component_management:`.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct ComponentManagement {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_rules: Option<Component>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub individual_components: Vec<Component>,
+}
+
+/**
+ * Component is a struct that represents the default rules or an individual component.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Component {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flag_regexes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statuses: Vec<RuleStatus>,
+}
+
+/**
+ * RuleStatus is a struct that represents a status inside flag or component rules.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RuleStatus {
+    #[serde(rename = "type")]
+    pub status_type: StatusType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<Target>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<Percentage>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub informational: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<String>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub only_pulls: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_ci_failed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_not_found: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed_code_behavior: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carryforward_behavior: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag_coverage_not_uploaded_behavior: Option<String>,
+}
+
+/**
+ * StatusType is an enum that represents the kind of a status.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusType {
+    Project,
+    Patch,
+    Changes,
+}
+
+/**
+ * CommentConfig is a struct that represents `comment:`.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct CommentConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub behavior: Option<String>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub require_changes: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub require_base: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub require_head: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub hide_project_coverage: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub hide_comment_details: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub show_carryforward_flags: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub show_critical_paths: Option<bool>,
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub require_bundle_changes: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_change_threshold: Option<serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_n_builds: Option<u32>,
+}
+
+impl CommentConfig {
+    /**
+     * Returns the sections of layout, e.g. ["reach", "diff", "flags", "files"].
+     */
+    pub fn layout_sections(&self) -> Vec<String> {
+        match &self.layout {
+            Some(layout) => layout
+                .split(',')
+                .map(|section| section.trim().to_string())
+                .filter(|section| !section.is_empty())
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+/**
+ * GithubChecks is a struct that represents `github_checks:`.
+ */
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct GithubChecks {
+    #[serde(default, with = "yaml_bool", skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<bool>,
+}
+
+/**
+ * Toggle is an enum for settings that accept either a boolean or a detailed configuration.
+ * e.g. `comment: false` or `comment: {layout: "diff"}`
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Toggle<T> {
+    Enabled(bool),
+    Config(T),
+}
+
+impl<T> Toggle<T> {
+    /**
+     * Returns the configuration, or None if the setting is a boolean.
+     */
+    pub fn config(&self) -> Option<&T> {
+        match self {
+            Toggle::Enabled(_) => None,
+            Toggle::Config(config) => Some(config),
+        }
+    }
+
+    /**
+     * Returns false only if the setting is explicitly disabled.
+     */
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, Toggle::Enabled(false))
+    }
+}
+
+impl<T: Serialize> Serialize for Toggle<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Toggle::Enabled(enabled) => serializer.serialize_bool(*enabled),
+            Toggle::Config(config) => config.serialize(serializer),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Toggle<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ToggleVisitor<T>(std::marker::PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for ToggleVisitor<T> {
+            type Value = Toggle<T>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a boolean or a map")
+            }
+
+            fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+                Ok(Toggle::Enabled(v))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                match yaml_bool::parse(v) {
+                    Some(enabled) => Ok(Toggle::Enabled(enabled)),
+                    None => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+                }
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let config = T::deserialize(de::value::MapAccessDeserializer::new(map))?;
+                Ok(Toggle::Config(config))
+            }
+        }
+
+        deserializer.deserialize_any(ToggleVisitor(std::marker::PhantomData))
+    }
+}
+
+/**
+ * Percentage is a number written as `80`, `80.5` or `"80%"`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Percentage(pub f64);
+
+impl Percentage {
+    fn parse(s: &str) -> Option<Percentage> {
+        let s = s.trim();
+        let s = s.strip_suffix('%').unwrap_or(s);
+        s.trim().parse::<f64>().ok().map(Percentage)
+    }
+}
+
+impl Serialize for Percentage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{}%", self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Percentage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Target::deserialize(deserializer)? {
+            Target::Percent(value) => Ok(Percentage(value)),
+            Target::Auto => Err(de::Error::invalid_value(
+                de::Unexpected::Str("auto"),
+                &"a percentage",
+            )),
+        }
+    }
+}
+
+/**
+ * Target is an enum that represents a status target, either `auto` or a percentage.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    Auto,
+    Percent(f64),
+}
+
+impl Serialize for Target {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Target::Auto => serializer.serialize_str("auto"),
+            Target::Percent(value) => Percentage(*value).serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Target {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TargetVisitor;
+
+        impl<'de> Visitor<'de> for TargetVisitor {
+            type Value = Target;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("\"auto\" or a percentage like 80 or \"80%\"")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(Target::Percent(v as f64))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(Target::Percent(v as f64))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                Ok(Target::Percent(v))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                if v.trim() == "auto" {
+                    return Ok(Target::Auto);
+                }
+                match Percentage::parse(v) {
+                    Some(Percentage(value)) => Ok(Target::Percent(value)),
+                    None => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(TargetVisitor)
+    }
+}
+
+/**
+ * Range is a struct that represents `coverage.range`, written as `"70...100"` or `[70, 100]`.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Serialize for Range {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{}...{}", self.low, self.high))
+    }
+}
+
+impl<'de> Deserialize<'de> for Range {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RangeVisitor;
+
+        impl<'de> Visitor<'de> for RangeVisitor {
+            type Value = Range;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a range like \"70...100\" or [70, 100]")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                let parsed = v.split_once("..").and_then(|(low, high)| {
+                    let low = Percentage::parse(low)?.0;
+                    let high = Percentage::parse(high.trim_start_matches('.'))?.0;
+                    Some(Range { low, high })
+                });
+                parsed.ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let low: Target = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let high: Target = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                match (low, high) {
+                    (Target::Percent(low), Target::Percent(high)) => Ok(Range { low, high }),
+                    _ => Err(de::Error::invalid_value(de::Unexpected::Str("auto"), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(RangeVisitor)
+    }
+}
+
+/**
+ * yaml_bool accepts YAML 1.1 style booleans (yes/no/on/off) that codecov.yml commonly uses.
+ */
+mod yaml_bool {
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn parse(v: &str) -> Option<bool> {
+        match v.trim().to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "y" => Some(true),
+            "false" | "no" | "off" | "n" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<bool>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_bool(*value),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<bool>, D::Error> {
+        struct BoolVisitor;
+
+        impl<'de> Visitor<'de> for BoolVisitor {
+            type Value = Option<bool>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a boolean")
+            }
+
+            fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+                Ok(Some(v))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                match parse(v) {
+                    Some(v) => Ok(Some(v)),
+                    None => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+                }
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+        }
+
+        deserializer.deserialize_any(BoolVisitor)
+    }
+}
+
+/**
+ * Diagnostic is a struct that represents a problem found in codecov.yml.
+ * line and column are 1-based and None when the position is unknown.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub path: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+/**
+ * DiagnosticKind is an enum that represents the kind of a Diagnostic.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    UnknownKey,
+    InvalidValue,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, "{}:{}: ", line, column)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+impl CodecovConfig {
+    /**
+     * Parses codecov.yml. Unknown keys are ignored, use lint to report them.
+     */
+    pub fn from_yaml(source: &str) -> Result<CodecovConfig, Error> {
+        match serde_yaml::from_str::<CodecovConfig>(source) {
+            Ok(config) => Ok(config),
+            Err(e) => Err(Error::YamlError(e)),
+        }
+    }
+
+    /**
+     * Serializes this configuration back into YAML.
+     */
+    pub fn to_yaml(&self) -> Result<String, Error> {
+        match serde_yaml::to_string(self) {
+            Ok(yaml) => Ok(yaml),
+            Err(e) => Err(Error::YamlError(e)),
+        }
+    }
+
+    /**
+     * Returns a PathFixer configured with `fixes` and `ignore`.
+     */
+    pub fn path_fixer(&self) -> Result<PathFixer, Error> {
+        let fixes: Vec<&str> = self.fixes.iter().map(String::as_str).collect();
+        let ignore: Vec<&str> = self.ignore.iter().map(String::as_str).collect();
+        Ok(PathFixer::new().with_fixes(&fixes)?.with_ignore(&ignore))
+    }
+}
+
+/**
+ * Lints codecov.yml, reporting unknown keys and invalid values with their positions.
+ * An empty result means the file is valid.
+ */
+pub fn lint(source: &str) -> Vec<Diagnostic> {
+    let mut unknown: Vec<Vec<String>> = Vec::new();
+    let deserializer = serde_yaml::Deserializer::from_str(source);
+    let result = serde_ignored::deserialize(deserializer, |path| {
+        unknown.push(path_segments(&path));
+    });
+    let mut diagnostics: Vec<Diagnostic> = unknown
+        .into_iter()
+        .map(|segments| {
+            let (line, column) = match locate(source, &segments) {
+                Some((line, column)) => (Some(line), Some(column)),
+                None => (None, None),
+            };
+            let path = segments.join(".");
+            Diagnostic {
+                kind: DiagnosticKind::UnknownKey,
+                message: format!("unknown key `{}`", path),
+                path,
+                line,
+                column,
+            }
+        })
+        .collect();
+    let result: Result<CodecovConfig, serde_yaml::Error> = result;
+    if let Err(e) = result {
+        let location = e.location();
+        diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::InvalidValue,
+            path: String::new(),
+            line: location.as_ref().map(|l| l.line()),
+            column: location.as_ref().map(|l| l.column()),
+            message: e.to_string(),
+        });
+    }
+    diagnostics
+}
+
+fn path_segments(path: &serde_ignored::Path) -> Vec<String> {
+    let mut segments = match path {
+        serde_ignored::Path::Root => return vec![],
+        serde_ignored::Path::Seq { parent, .. }
+        | serde_ignored::Path::Map { parent, .. }
+        | serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => path_segments(parent),
+    };
+    match path {
+        serde_ignored::Path::Seq { index, .. } => segments.push(index.to_string()),
+        serde_ignored::Path::Map { key, .. } => segments.push(key.clone()),
+        _ => {}
+    }
+    segments
+}
+
+/**
+ * Finds the 1-based line and column of a key path in block style YAML.
+ * Numeric segments are sequence indexes. Returns None for flow style or if not found.
+ */
+fn locate(source: &str, segments: &[String]) -> Option<(usize, usize)> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut start = 0;
+    let mut parent_indent: Option<usize> = None;
+    let mut found = None;
+    for segment in segments {
+        let mut item = 0;
+        let index = segment.parse::<usize>().ok();
+        let mut position = None;
+        for (i, line) in lines.iter().enumerate().skip(start) {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let indent = line.len() - trimmed.len();
+            if i != start && parent_indent.map(|p| indent <= p).unwrap_or(false) {
+                break;
+            }
+            match index {
+                Some(index) => {
+                    if trimmed.starts_with("- ") || trimmed == "-" {
+                        if item == index {
+                            position = Some((i, indent));
+                            break;
+                        }
+                        item += 1;
+                    }
+                }
+                None => {
+                    let (column, key_line) = match trimmed.strip_prefix("- ") {
+                        Some(rest) => (indent + 2, rest.trim_start()),
+                        None => (indent, trimmed),
+                    };
+                    let key = key_line
+                        .split_once(':')
+                        .map(|(key, _)| key.trim().trim_matches(['"', '\'']));
+                    if key == Some(segment.as_str()) {
+                        position = Some((i, column));
+                        break;
+                    }
+                }
+            }
+        }
+        let (line, column) = position?;
+        found = Some((line + 1, column + 1));
+        start = if index.is_some() { line } else { line + 1 };
+        parent_indent = Some(column);
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YAML: &str = r#"
+codecov:
+  require_ci_to_pass: yes
+  notify:
+    after_n_builds: 2
+coverage:
+  precision: 2
+  round: down
+  range: "70...100"
+  status:
+    project:
+      default:
+        target: auto
+        threshold: 1%
+        flags:
+          - unit
+    patch:
+      default:
+        target: 80
+    changes: off
+ignore:
+  - "tests/**"
+fixes:
+  - "/app/::"
+flags:
+  unit:
+    paths:
+      - src/
+    carryforward: true
+component_management:
+  individual_components:
+    - component_id: core
+      name: Core
+      paths:
+        - src/core/**
+      statuses:
+        - type: project
+          target: 90%
+comment:
+  layout: "reach, diff, flags, files"
+  require_changes: false
+"#;
+
+    #[test]
+    fn test_from_yaml() {
+        let config = CodecovConfig::from_yaml(YAML).unwrap();
+        let codecov = config.codecov.as_ref().unwrap();
+        assert_eq!(codecov.require_ci_to_pass, Some(true));
+        let coverage = config.coverage.as_ref().unwrap();
+        assert_eq!(coverage.round, Some(Round::Down));
+        assert_eq!(
+            coverage.range,
+            Some(Range {
+                low: 70.0,
+                high: 100.0
+            })
+        );
+        let status = coverage.status.as_ref().unwrap().config().unwrap();
+        let project = status.project.as_ref().unwrap().config().unwrap();
+        assert_eq!(project["default"].target, Some(Target::Auto));
+        assert_eq!(project["default"].threshold, Some(Percentage(1.0)));
+        let patch = status.patch.as_ref().unwrap().config().unwrap();
+        assert_eq!(patch["default"].target, Some(Target::Percent(80.0)));
+        assert_eq!(status.changes, Some(Toggle::Enabled(false)));
+        assert_eq!(config.flags["unit"].carryforward, Some(true));
+        let components = &config.component_management.as_ref().unwrap();
+        let core = &components.individual_components[0];
+        assert_eq!(core.statuses[0].status_type, StatusType::Project);
+        assert_eq!(core.statuses[0].target, Some(Target::Percent(90.0)));
+        let comment = config.comment.as_ref().unwrap().config().unwrap();
+        assert_eq!(
+            comment.layout_sections(),
+            vec!["reach", "diff", "flags", "files"]
+        );
+        assert!(lint(YAML).is_empty());
+    }
+
+    /**
+     * Every key of the codecov.yml reference (https://docs.codecov.com/docs/codecovyml-reference).
+     */
+    const REFERENCE_YAML: &str = r#"
+codecov:
+  url: "https://codecov.example.com"
+  slug: "kitsuyui/rust-codecov"
+  token: "<token>"
+  bot: "codecov-io"
+  branch: main
+  ci:
+    - github.com
+    - "!travis-ci.org"
+  archive:
+    uploads: true
+  max_report_age: 24
+  require_ci_to_pass: true
+  strict_yaml_branch: main
+  disable_default_path_fixes: false
+  allow_coverage_offsets: true
+  allow_pseudo_compare: true
+  notify:
+    after_n_builds: 2
+    countdown: 10
+    delay: 5
+    wait_for_ci: true
+    manual_trigger: false
+    notify_error: true
+coverage:
+  precision: 2
+  round: down
+  range: 70..100
+  notify:
+    slack:
+      default:
+        url: "https://hooks.slack.com/services/xxx"
+        threshold: 1%
+  status:
+    default_rules:
+      flag_coverage_not_uploaded_behavior: include
+      carryforward_behavior: pass
+    project:
+      default:
+        target: auto
+        threshold: 0%
+        base: auto
+        flags:
+          - unit
+        paths:
+          - "src"
+        branches:
+          - main
+        if_ci_failed: error
+        if_not_found: success
+        informational: false
+        only_pulls: false
+        removed_code_behavior: adjust_base
+        carryforward_behavior: exclude
+        flag_coverage_not_uploaded_behavior: exclude
+    patch:
+      default:
+        target: 80%
+    changes: false
+ignore:
+  - "tests/**/*"
+fixes:
+  - "before/::after/"
+flags:
+  unit:
+    paths:
+      - src/
+    ignore:
+      - src/generated/
+    carryforward: true
+    carryforward_mode: all
+    after_n_builds: 1
+    joined: true
+    required: false
+flag_management:
+  default_rules:
+    carryforward: true
+    carryforward_mode: labels
+    after_n_builds: 1
+    statuses:
+      - type: project
+        target: auto
+        threshold: 1%
+      - type: patch
+        target: 90%
+  individual_flags:
+    - name: feature_1
+      paths:
+        - feature_1/
+      ignore:
+        - feature_1/vendor/
+      statuses:
+        - type: project
+          name_prefix: feature-
+          target: 80%
+          base: auto
+          branches:
+            - main
+          only_pulls: true
+          if_ci_failed: error
+          if_not_found: failure
+          informational: true
+          removed_code_behavior: fully_covered_patch
+component_management:
+  default_rules:
+    flag_regexes:
+      - "unit.*"
+    statuses:
+      - type: project
+        target: auto
+  individual_components:
+    - component_id: module_a
+      name: Module A
+      paths:
+        - src/module_a/**
+      statuses:
+        - type: patch
+          target: 90%
+comment:
+  layout: "diff, flags, files"
+  behavior: default
+  require_changes: false
+  require_base: false
+  require_head: true
+  require_bundle_changes: false
+  bundle_change_threshold: "1Kb"
+  hide_project_coverage: false
+  hide_comment_details: false
+  show_carryforward_flags: true
+  show_critical_paths: true
+  branches:
+    - main
+  after_n_builds: 2
+github_checks:
+  annotations: true
+slack_app: false
+parsers:
+  gcov:
+    branch_detection:
+      conditional: true
+      loop: true
+      method: false
+      macro: false
+  javascript:
+    enable_partials: true
+  v1:
+    include_full_missed_files: true
+profiling:
+  critical_files_paths:
+    - src/lib.rs
+bundle_analysis:
+  warning_threshold: "5%"
+  status: informational
+test_analytics:
+  flake_detection: true
+ai_pr_review:
+  enabled: true
+  method: auto
+beta_groups:
+  - labs
+cli:
+  plugins:
+    pycoverage:
+      report_type: json
+  runners:
+    python:
+      collect_tests_options:
+        - "--ignore-glob=tests/slow/*"
+"#;
+
+    #[test]
+    fn test_lint_reference() {
+        assert_eq!(lint(REFERENCE_YAML), vec![]);
+        let config = CodecovConfig::from_yaml(REFERENCE_YAML).unwrap();
+        let codecov = config.codecov.as_ref().unwrap();
+        assert_eq!(codecov.archive.as_ref().unwrap().uploads, Some(true));
+        assert_eq!(config.slack_app, Some(Toggle::Enabled(false)));
+        let comment = config.comment.as_ref().unwrap().config().unwrap();
+        assert_eq!(comment.show_carryforward_flags, Some(true));
+        assert!(config.cli.is_some());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let config = CodecovConfig::from_yaml(YAML).unwrap();
+        let yaml = config.to_yaml().unwrap();
+        assert_eq!(CodecovConfig::from_yaml(&yaml).unwrap(), config);
+    }
+
+    #[test]
+    fn test_lint_unknown_keys() {
+        let yaml = r#"
+coverage:
+  status:
+    project:
+      default:
+        target: auto
+        maximum: 1%
+component_management:
+  individual_components:
+    - component_id: core
+      directories: [src]
+unknown: 1
+"#;
+        let diagnostics = lint(yaml);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::UnknownKey);
+        assert_eq!(
+            diagnostics[0].path,
+            "coverage.status.project.default.maximum"
+        );
+        assert_eq!(
+            (diagnostics[0].line, diagnostics[0].column),
+            (Some(7), Some(9))
+        );
+        assert_eq!(
+            diagnostics[1].path,
+            "component_management.individual_components.0.directories"
+        );
+        assert_eq!(
+            (diagnostics[1].line, diagnostics[1].column),
+            (Some(11), Some(7))
+        );
+        assert_eq!(
+            (diagnostics[2].line, diagnostics[2].column),
+            (Some(12), Some(1))
+        );
+    }
+
+    #[test]
+    fn test_lint_invalid_value() {
+        let yaml = "coverage:\n  status:\n    project:\n      default:\n        target: high\n";
+        let diagnostics = lint(yaml);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidValue);
+        assert_eq!(diagnostics[0].line, Some(5));
+        assert!(CodecovConfig::from_yaml(yaml).is_err());
+    }
+
+    #[test]
+    fn test_path_fixer() {
+        let config = CodecovConfig::from_yaml(YAML).unwrap();
+        let fixer = config.path_fixer().unwrap();
+        assert_eq!(fixer.fix("/app/src/lib.rs"), Some("src/lib.rs".to_string()));
+        assert_eq!(fixer.fix("/app/tests/it.rs"), None);
+    }
+}
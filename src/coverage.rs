@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+
+use crate::totals::{Diff, Totals};
+
+/**
+ * LineCoverage is an enum that represents the coverage of a single line.
+ * Partial lines carry the number of covered and total branches like Codecov's "1/2" notation.
+ * A line with all branches covered ("2/2") counts as a hit and one with none ("0/2") as a miss,
+ * but they are kept as Partial so that the branch counts are not lost.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineCoverage {
+    Hit(u64),
+    Miss,
+    Partial {
+        covered: u32,
+        total: u32,
+    },
+    /**
+     * PartialUnknown is a partial line whose branch counts are unknown,
+     * e.g. the `[line, 2]` line coverage of the API.
+     */
+    PartialUnknown,
+    /**
+     * PartialUntaken is a line that was executed although none of its branches were taken,
+     * e.g. a condition of llvm-cov that was never evaluated. Unlike "0/2" it is not a miss.
+     */
+    PartialUntaken {
+        total: u32,
+    },
+}
+
+impl LineCoverage {
+    /**
+     * Returns true if the line was executed at least once.
+     */
+    pub fn is_covered(&self) -> bool {
+        match self {
+            LineCoverage::Hit(_) => true,
+            LineCoverage::Miss => false,
+            LineCoverage::Partial { covered, .. } => *covered > 0,
+            LineCoverage::PartialUnknown => true,
+            LineCoverage::PartialUntaken { .. } => true,
+        }
+    }
+
+    /**
+     * Returns true if only some of the branches of the line were taken.
+     */
+    pub fn is_partial(&self) -> bool {
+        match self {
+            LineCoverage::Partial { covered, total } => *covered > 0 && covered < total,
+            LineCoverage::PartialUnknown | LineCoverage::PartialUntaken { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/**
+ * LineSession is a struct that represents the coverage of a line in a single session (upload).
+ * session is an index into CoverageReport::sessions.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineSession {
+    pub session: usize,
+    pub coverage: LineCoverage,
+}
+
+/**
+ * FileCoverage is a struct that represents the line-level coverage of a file.
+ * lines and sessions are keyed by 1-based line number.
+ * sessions is empty for reports that were not tagged with a Session.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileCoverage {
+    pub lines: BTreeMap<u32, LineCoverage>,
+    pub sessions: BTreeMap<u32, Vec<LineSession>>,
+    pub branches: usize,
+    pub methods: usize,
+}
+
+/**
+ * Session is a struct that represents a single upload that contributed to a report.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Session {
+    pub name: Option<String>,
+    pub flags: Vec<String>,
+}
+
+impl Session {
+    pub fn new(name: &str, flags: &[&str]) -> Session {
+        Session {
+            name: Some(name.to_string()),
+            flags: flags.iter().map(|flag| flag.to_string()).collect(),
+        }
+    }
+}
+
+/**
+ * CoverageReport is a struct that represents a parsed coverage report.
+ * files is keyed by the path as it appears in the report.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoverageReport {
+    pub files: BTreeMap<String, FileCoverage>,
+    pub sessions: Vec<Session>,
+}
+
+/**
+ * Counts is a helper to accumulate hits, misses and partials.
+ */
+#[derive(Debug, Default)]
+struct Counts {
+    files: usize,
+    hits: usize,
+    misses: usize,
+    partials: usize,
+    branches: usize,
+    methods: usize,
+}
+
+impl Counts {
+    fn add_file(&mut self, file: &FileCoverage) {
+        self.files += 1;
+        for line in file.lines.values() {
+            if line.is_partial() {
+                self.partials += 1;
+            } else if line.is_covered() {
+                self.hits += 1;
+            } else {
+                self.misses += 1;
+            }
+        }
+        self.branches += file.branches;
+        self.methods += file.methods;
+    }
+
+    fn into_totals(self, sessions: usize) -> Totals {
+        let lines = self.hits + self.misses + self.partials;
+        let coverage = if lines == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lines as f64 * 100.0
+        };
+        Totals {
+            files: self.files,
+            lines,
+            hits: self.hits,
+            misses: self.misses,
+            partials: self.partials,
+            coverage,
+            branches: self.branches,
+            methods: self.methods,
+            sessions,
+            complexity: 0.0,
+            complexity_total: 0.0,
+            complexity_ratio: 0.0,
+            messages: 0,
+            diff: Diff::Value(0),
+        }
+    }
+}
+
+impl FileCoverage {
+    /**
+     * Returns the totals for this file.
+     * Coverage is computed as hits / (hits + misses + partials) like Codecov does.
+     */
+    pub fn totals(&self) -> Totals {
+        let mut counts = Counts::default();
+        counts.add_file(self);
+        counts.into_totals(0)
+    }
+}
+
+impl CoverageReport {
+    /**
+     * Returns the totals for the whole report.
+     * sessions is the number of sessions merged into this report.
+     */
+    pub fn totals(&self) -> Totals {
+        let mut counts = Counts::default();
+        for file in self.files.values() {
+            counts.add_file(file);
+        }
+        counts.into_totals(self.sessions.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_totals() {
+        let mut file = FileCoverage::default();
+        file.lines.insert(1, LineCoverage::Hit(3));
+        file.lines.insert(2, LineCoverage::Miss);
+        file.lines.insert(
+            3,
+            LineCoverage::Partial {
+                covered: 1,
+                total: 2,
+            },
+        );
+        file.lines.insert(4, LineCoverage::Hit(1));
+        file.branches = 2;
+        file.methods = 1;
+        let mut report = CoverageReport::default();
+        report.files.insert("src/lib.rs".to_string(), file);
+        report
+            .files
+            .insert("src/empty.rs".to_string(), FileCoverage::default());
+
+        let totals = report.totals();
+        assert_eq!(totals.files, 2);
+        assert_eq!(totals.lines, 4);
+        assert_eq!(totals.hits, 2);
+        assert_eq!(totals.misses, 1);
+        assert_eq!(totals.partials, 1);
+        assert_eq!(totals.branches, 2);
+        assert_eq!(totals.methods, 1);
+        assert_eq!(totals.coverage, 50.0);
+
+        let empty = report.files["src/empty.rs"].totals();
+        assert_eq!(empty.coverage, 0.0);
+    }
+}
@@ -0,0 +1,422 @@
+/**
+ * Parsing of unified and git diffs, used to compute patch coverage.
+ */
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::coverage::{CoverageReport, FileCoverage};
+use crate::totals::Totals;
+
+/**
+ * AddedLines maps a file path (in the new revision) to the line numbers added to it.
+ */
+pub type AddedLines = BTreeMap<String, BTreeSet<u32>>;
+
+/**
+ * ChangeKind is an enum that represents how a file was changed.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    Copied,
+}
+
+/**
+ * FileDiff is a struct that represents the changes to a single file.
+ * old_path is None for new files and new_path is None for deleted files.
+ * added holds line numbers of the new file and removed line numbers of the old file.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub kind: ChangeKind,
+    pub binary: bool,
+    pub added: BTreeSet<u32>,
+    pub removed: BTreeSet<u32>,
+}
+
+impl FileDiff {
+    fn new() -> FileDiff {
+        FileDiff {
+            old_path: None,
+            new_path: None,
+            kind: ChangeKind::Modified,
+            binary: false,
+            added: BTreeSet::new(),
+            removed: BTreeSet::new(),
+        }
+    }
+
+    /**
+     * Returns the path of the file after the change, or before it for deleted files.
+     */
+    pub fn path(&self) -> Option<&str> {
+        self.new_path.as_deref().or(self.old_path.as_deref())
+    }
+
+    fn finish(mut self) -> FileDiff {
+        if self.kind == ChangeKind::Modified {
+            self.kind = match (&self.old_path, &self.new_path) {
+                (None, Some(_)) => ChangeKind::Added,
+                (Some(_), None) => ChangeKind::Deleted,
+                (Some(old), Some(new)) if old != new => ChangeKind::Renamed,
+                _ => ChangeKind::Modified,
+            };
+        }
+        self
+    }
+}
+
+/**
+ * Hunk keeps track of the position inside a hunk.
+ */
+struct Hunk {
+    old_line: u32,
+    new_line: u32,
+    old_remaining: u32,
+    new_remaining: u32,
+}
+
+impl Hunk {
+    fn is_open(&self) -> bool {
+        self.old_remaining > 0 || self.new_remaining > 0
+    }
+}
+
+/**
+ * Parses a unified diff (optionally with git extended headers) into per-file changes.
+ */
+pub fn parse(diff: &str) -> Vec<FileDiff> {
+    let mut files = Vec::new();
+    let mut current: Option<FileDiff> = None;
+    let mut seen_new_header = false;
+    let mut hunk: Option<Hunk> = None;
+
+    for line in diff.lines() {
+        if let (Some(h), Some(file)) = (hunk.as_mut(), current.as_mut()) {
+            if h.is_open() {
+                let consumed = match line.chars().next() {
+                    Some('+') => {
+                        file.added.insert(h.new_line);
+                        h.new_line += 1;
+                        h.new_remaining = h.new_remaining.saturating_sub(1);
+                        true
+                    }
+                    Some('-') => {
+                        file.removed.insert(h.old_line);
+                        h.old_line += 1;
+                        h.old_remaining = h.old_remaining.saturating_sub(1);
+                        true
+                    }
+                    Some(' ') | None => {
+                        h.old_line += 1;
+                        h.new_line += 1;
+                        h.old_remaining = h.old_remaining.saturating_sub(1);
+                        h.new_remaining = h.new_remaining.saturating_sub(1);
+                        true
+                    }
+                    Some('\\') => true,
+                    _ => false,
+                };
+                if consumed {
+                    continue;
+                }
+            }
+        }
+        if line.starts_with('\\') {
+            // "\ No newline at end of file" right after the last line of a hunk.
+            continue;
+        }
+        hunk = None;
+
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            files.extend(current.take().map(FileDiff::finish));
+            let mut file = FileDiff::new();
+            let (old_path, new_path) = split_git_paths(rest);
+            file.old_path = old_path;
+            file.new_path = new_path;
+            current = Some(file);
+            seen_new_header = false;
+        } else if let Some(rest) = line.strip_prefix("--- ") {
+            if current.is_none() || seen_new_header {
+                files.extend(current.take().map(FileDiff::finish));
+                current = Some(FileDiff::new());
+                seen_new_header = false;
+            }
+            if let Some(file) = current.as_mut() {
+                file.old_path = header_path(rest, "a/");
+            }
+        } else if let Some(rest) = line.strip_prefix("+++ ") {
+            if let Some(file) = current.as_mut() {
+                file.new_path = header_path(rest, "b/");
+            }
+            seen_new_header = true;
+        } else if let Some(file) = current.as_mut() {
+            if line.starts_with("new file mode") {
+                file.kind = ChangeKind::Added;
+                file.old_path = None;
+            } else if line.starts_with("deleted file mode") {
+                file.kind = ChangeKind::Deleted;
+                file.new_path = None;
+            } else if let Some(path) = line.strip_prefix("rename from ") {
+                file.kind = ChangeKind::Renamed;
+                file.old_path = Some(unquote(path));
+            } else if let Some(path) = line.strip_prefix("rename to ") {
+                file.kind = ChangeKind::Renamed;
+                file.new_path = Some(unquote(path));
+            } else if let Some(path) = line.strip_prefix("copy from ") {
+                file.kind = ChangeKind::Copied;
+                file.old_path = Some(unquote(path));
+            } else if let Some(path) = line.strip_prefix("copy to ") {
+                file.kind = ChangeKind::Copied;
+                file.new_path = Some(unquote(path));
+            } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+                file.binary = true;
+            } else if let Some(header) = line.strip_prefix("@@ ") {
+                hunk = parse_hunk_header(header);
+            }
+        }
+    }
+    files.extend(current.map(FileDiff::finish));
+    files
+}
+
+/**
+ * Returns the lines added per file by a diff. Deleted and binary files are skipped.
+ */
+pub fn added_lines(diff: &str) -> AddedLines {
+    let mut added = AddedLines::new();
+    for file in parse(diff) {
+        if file.binary || file.added.is_empty() {
+            continue;
+        }
+        if let Some(path) = file.new_path {
+            added.entry(path).or_default().extend(file.added);
+        }
+    }
+    added
+}
+
+/**
+ * Returns the part of a report covering only the added lines.
+ */
+pub fn patch_report(report: &CoverageReport, added: &AddedLines) -> CoverageReport {
+    let mut patch = CoverageReport {
+        sessions: report.sessions.clone(),
+        ..CoverageReport::default()
+    };
+    for (path, lines) in added {
+        let file = match report.files.get(path) {
+            Some(file) => file,
+            None => continue,
+        };
+        let mut patch_file = FileCoverage::default();
+        for line in lines {
+            if let Some(coverage) = file.lines.get(line) {
+                patch_file.lines.insert(*line, coverage.clone());
+            }
+            if let Some(sessions) = file.sessions.get(line) {
+                patch_file.sessions.insert(*line, sessions.clone());
+            }
+        }
+        if !patch_file.lines.is_empty() {
+            patch.files.insert(path.clone(), patch_file);
+        }
+    }
+    patch
+}
+
+/**
+ * Returns the totals of the added lines, i.e. the patch coverage.
+ */
+pub fn patch_totals(report: &CoverageReport, added: &AddedLines) -> Totals {
+    patch_report(report, added).totals()
+}
+
+/**
+ * Splits "a/old b/new" of a "diff --git" line.
+ * Unquoted paths may contain spaces, so the split where both sides are equal is preferred.
+ */
+fn split_git_paths(rest: &str) -> (Option<String>, Option<String>) {
+    if let Some(quoted) = rest.strip_prefix('"') {
+        if let Some(end) = quoted.find('"') {
+            let old = unquote(&rest[..end + 2]);
+            let new = unquote(rest[end + 2..].trim());
+            return (strip(&old, "a/"), strip(&new, "b/"));
+        }
+    }
+    let splits: Vec<usize> = rest.match_indices(" b/").map(|(i, _)| i).collect();
+    let index = splits
+        .iter()
+        .find(|i| rest[..**i].strip_prefix("a/") == Some(&rest[**i + 3..]))
+        .or(splits.first());
+    match index {
+        Some(i) => (strip(&rest[..*i], "a/"), strip(&rest[*i + 1..], "b/")),
+        None => (None, None),
+    }
+}
+
+fn header_path(rest: &str, prefix: &str) -> Option<String> {
+    // A tab separates the path from an optional timestamp.
+    let path = rest.split('\t').next().unwrap_or(rest).trim_end();
+    let path = unquote(path);
+    if path == "/dev/null" {
+        return None;
+    }
+    strip(&path, prefix)
+}
+
+fn strip(path: &str, prefix: &str) -> Option<String> {
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+fn unquote(path: &str) -> String {
+    let path = path.trim();
+    match path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) {
+        Some(inner) => inner.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => path.to_string(),
+    }
+}
+
+/**
+ * Parses a hunk header like "-1,3 +1,4 @@ fn main()".
+ */
+fn parse_hunk_header(header: &str) -> Option<Hunk> {
+    let mut parts = header.split_whitespace();
+    let (old_line, old_remaining) = parse_range(parts.next()?.strip_prefix('-')?)?;
+    let (new_line, new_remaining) = parse_range(parts.next()?.strip_prefix('+')?)?;
+    Some(Hunk {
+        old_line,
+        new_line,
+        old_remaining,
+        new_remaining,
+    })
+}
+
+fn parse_range(range: &str) -> Option<(u32, u32)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coverage::LineCoverage;
+
+    const DIFF: &str = r#"diff --git a/src/lib.rs b/src/lib.rs
+index 83db48f..bf269f4 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,4 @@
+ fn a() {}
+--- removed line that looks like a header
++fn b() {}
++fn c() {}
+ fn d() {}
+@@ -10,2 +11,2 @@ fn e() {
+-    1
++    2
+ }
+\ No newline at end of file
+diff --git a/old name.rs b/new name.rs
+similarity index 90%
+rename from old name.rs
+rename to new name.rs
+index 1111111..2222222 100644
+--- a/old name.rs
++++ b/new name.rs
+@@ -5 +5 @@
+-x
++y
+diff --git a/src/new.rs b/src/new.rs
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/src/new.rs
+@@ -0,0 +1,2 @@
++fn new() {}
++
+diff --git a/src/gone.rs b/src/gone.rs
+deleted file mode 100644
+index 4444444..0000000
+--- a/src/gone.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-fn gone() {}
+diff --git a/logo.png b/logo.png
+index 5555555..6666666 100644
+Binary files a/logo.png and b/logo.png differ
+"#;
+
+    #[test]
+    fn test_parse() {
+        let files = parse(DIFF);
+        assert_eq!(files.len(), 5);
+
+        let lib = &files[0];
+        assert_eq!(lib.kind, ChangeKind::Modified);
+        assert_eq!(lib.path(), Some("src/lib.rs"));
+        assert_eq!(lib.added, BTreeSet::from([2, 3, 11]));
+        assert_eq!(lib.removed, BTreeSet::from([2, 10]));
+
+        let renamed = &files[1];
+        assert_eq!(renamed.kind, ChangeKind::Renamed);
+        assert_eq!(renamed.old_path.as_deref(), Some("old name.rs"));
+        assert_eq!(renamed.new_path.as_deref(), Some("new name.rs"));
+        assert_eq!(renamed.added, BTreeSet::from([5]));
+
+        let new = &files[2];
+        assert_eq!(new.kind, ChangeKind::Added);
+        assert_eq!(new.old_path, None);
+        assert_eq!(new.added, BTreeSet::from([1, 2]));
+
+        let gone = &files[3];
+        assert_eq!(gone.kind, ChangeKind::Deleted);
+        assert_eq!(gone.path(), Some("src/gone.rs"));
+        assert_eq!(gone.removed, BTreeSet::from([1]));
+
+        let binary = &files[4];
+        assert!(binary.binary);
+        assert_eq!(binary.path(), Some("logo.png"));
+    }
+
+    #[test]
+    fn test_parse_plain_unified_diff() {
+        let diff = "--- a.txt\t2023-01-01 00:00:00\n+++ a.txt\t2023-01-02 00:00:00\n@@ -1,2 +1,2 @@\n-a\n+b\n c\n--- b.txt\n+++ b.txt\n@@ -3,0 +4 @@\n+d\n";
+        let files = parse(diff);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path(), Some("a.txt"));
+        assert_eq!(files[0].added, BTreeSet::from([1]));
+        assert_eq!(files[1].added, BTreeSet::from([4]));
+    }
+
+    #[test]
+    fn test_patch_totals() {
+        let mut file = FileCoverage::default();
+        file.lines.insert(1, LineCoverage::Hit(1));
+        file.lines.insert(2, LineCoverage::Hit(1));
+        file.lines.insert(3, LineCoverage::Miss);
+        file.lines.insert(
+            11,
+            LineCoverage::Partial {
+                covered: 1,
+                total: 2,
+            },
+        );
+        let mut report = CoverageReport::default();
+        report.files.insert("src/lib.rs".to_string(), file);
+
+        let added = added_lines(DIFF);
+        assert_eq!(added.len(), 3);
+        let totals = patch_totals(&report, &added);
+        assert_eq!(totals.files, 1);
+        assert_eq!(totals.lines, 3);
+        assert_eq!(totals.hits, 1);
+        assert_eq!(totals.partials, 1);
+    }
+}
@@ -0,0 +1,115 @@
+/**
+ * Endpoint is the kind of API resource a URL points to, e.g. to set a cache TTL per endpoint.
+ */
+use std::fmt;
+
+use crate::url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Endpoint {
+    Repos,
+    Repo,
+    Branches,
+    BranchDetail,
+    Commits,
+    Commit,
+    Uploads,
+    Other,
+}
+
+impl Endpoint {
+    /**
+     * Returns the endpoint of url, whose path is relative to api_endpoint
+     * (e.g. url::API_ENDPOINT or the endpoint of a self-hosted Codecov).
+     */
+    pub fn classify(api_endpoint: &Url, url: &Url) -> Endpoint {
+        let base = non_empty_segments(api_endpoint);
+        let segments = non_empty_segments(url);
+        let rest = match segments.strip_prefix(base.as_slice()) {
+            Some(rest) => rest,
+            None => return Endpoint::Other,
+        };
+        match rest {
+            [_, _, "repos"] => Endpoint::Repos,
+            [_, _, "repos", _] => Endpoint::Repo,
+            [_, _, "repos", _, "branches"] => Endpoint::Branches,
+            [_, _, "repos", _, "branches", _] => Endpoint::BranchDetail,
+            [_, _, "repos", _, "commits"] => Endpoint::Commits,
+            [_, _, "repos", _, "commits", _] => Endpoint::Commit,
+            [_, _, "repos", _, "commits", _, "uploads"] => Endpoint::Uploads,
+            _ => Endpoint::Other,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Endpoint::Repos => "repos",
+            Endpoint::Repo => "repo",
+            Endpoint::Branches => "branches",
+            Endpoint::BranchDetail => "branch_detail",
+            Endpoint::Commits => "commits",
+            Endpoint::Commit => "commit",
+            Endpoint::Uploads => "uploads",
+            Endpoint::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+fn non_empty_segments(url: &Url) -> Vec<&str> {
+    match url.path_segments() {
+        Some(segments) => segments.filter(|s| !s.is_empty()).collect(),
+        None => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::url::{api_url, API_ENDPOINT};
+
+    #[test]
+    fn test_classify() {
+        let base = Url::parse(API_ENDPOINT).unwrap();
+        let classify = |segments: &[&str]| Endpoint::classify(&base, &api_url(segments).unwrap());
+        assert_eq!(classify(&["github", "kitsuyui", "repos"]), Endpoint::Repos);
+        assert_eq!(
+            classify(&["github", "kitsuyui", "repos", "rust-codecov"]),
+            Endpoint::Repo
+        );
+        assert_eq!(
+            classify(&[
+                "github",
+                "kitsuyui",
+                "repos",
+                "rust-codecov",
+                "branches",
+                "a/b"
+            ]),
+            Endpoint::BranchDetail
+        );
+        assert_eq!(
+            classify(&[
+                "github",
+                "kitsuyui",
+                "repos",
+                "rust-codecov",
+                "commits",
+                "abc",
+                "uploads"
+            ]),
+            Endpoint::Uploads
+        );
+        assert_eq!(classify(&["github"]), Endpoint::Other);
+
+        let self_hosted = Url::parse("https://codecov.example.com/api/v2/").unwrap();
+        let url = Url::parse("https://codecov.example.com/api/v2/gh/org/repos?page=2").unwrap();
+        assert_eq!(Endpoint::classify(&self_hosted, &url), Endpoint::Repos);
+        assert_eq!(Endpoint::Commits.to_string(), "commits");
+    }
+}
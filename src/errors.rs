@@ -6,4 +6,84 @@ pub enum Error {
     ReqwestError(reqwest::Error),
     EnvError(std::env::VarError),
     DeserializeError(serde_json::Error),
+    SerializeError(serde_json::Error),
+    FormatError(String),
+    ConfigError(String),
+    YamlError(serde_yaml::Error),
+    ParseError(String),
+    UrlError(::url::ParseError),
+    NotFoundError(String),
+    TimeoutError(String),
+    CancelledError,
+    IoError(std::io::Error),
+    ReplayError(String),
+    /**
+     * HttpError is a response with an error status other than 404 (e.g. 401, 403 or 5xx).
+     * 404 responses are reported by each API as not found instead.
+     */
+    HttpError {
+        status: u16,
+        detail: String,
+    },
+    #[cfg(feature = "async")]
+    JoinError(tokio::task::JoinError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::ReqwestError(e) => write!(f, "request failed: {}", e),
+            Error::EnvError(e) => write!(f, "environment variable error: {}", e),
+            Error::DeserializeError(e) => write!(f, "failed to deserialize: {}", e),
+            Error::SerializeError(e) => write!(f, "failed to serialize: {}", e),
+            Error::FormatError(message) => write!(f, "invalid format: {}", message),
+            Error::ConfigError(message) => write!(f, "invalid config: {}", message),
+            Error::YamlError(e) => write!(f, "invalid YAML: {}", e),
+            Error::ParseError(message) => write!(f, "failed to parse: {}", message),
+            Error::UrlError(e) => write!(f, "invalid URL: {}", e),
+            Error::NotFoundError(detail) => write!(f, "not found: {}", detail),
+            Error::TimeoutError(message) => write!(f, "timed out: {}", message),
+            Error::CancelledError => write!(f, "cancelled"),
+            Error::IoError(e) => write!(f, "I/O error: {}", e),
+            Error::ReplayError(message) => write!(f, "replay failed: {}", message),
+            Error::HttpError { status, detail } => write!(f, "HTTP {}: {}", status, detail),
+            #[cfg(feature = "async")]
+            Error::JoinError(e) => write!(f, "task failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::ReqwestError(e) => Some(e),
+            Error::EnvError(e) => Some(e),
+            Error::DeserializeError(e) | Error::SerializeError(e) => Some(e),
+            Error::YamlError(e) => Some(e),
+            Error::UrlError(e) => Some(e),
+            Error::IoError(e) => Some(e),
+            #[cfg(feature = "async")]
+            Error::JoinError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let error = Error::HttpError {
+            status: 401,
+            detail: "Invalid token.".to_string(),
+        };
+        assert_eq!(error.to_string(), "HTTP 401: Invalid token.");
+        assert_eq!(
+            Error::NotFoundError("Not found.".to_string()).to_string(),
+            "not found: Not found."
+        );
+        assert_eq!(Error::CancelledError.to_string(), "cancelled");
+    }
 }
@@ -0,0 +1,6 @@
+/**
+ * Parsers (and writers) for coverage report formats.
+ * Every format is converted into the common line-level model in crate::coverage.
+ */
+pub mod codecov_json;
+pub mod llvm_cov;
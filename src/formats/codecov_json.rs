@@ -0,0 +1,253 @@
+/**
+ * Reader and writer for Codecov's own JSON coverage format.
+ * https://docs.codecov.com/docs/codecov-custom-coverage-format
+ *
+ * ```json
+ * {"coverage": {"src/lib.rs": {"1": 1, "2": 0, "3": "1/2", "4": null}}}
+ * ```
+ * A line may be given as a hit count, a "covered/total" branch string or null (not tracked).
+ * Lines of a file may also be given as an array indexed by line number.
+ */
+use serde_json::{Map, Value};
+
+use crate::coverage::{CoverageReport, FileCoverage, LineCoverage};
+use crate::errors::Error;
+
+/**
+ * Parses a Codecov JSON string into a CoverageReport.
+ */
+pub fn parse(json: &str) -> Result<CoverageReport, Error> {
+    let value = match serde_json::from_str::<Value>(json) {
+        Ok(value) => value,
+        Err(e) => return Err(Error::DeserializeError(e)),
+    };
+    parse_value(value)
+}
+
+/**
+ * Parses a Codecov JSON report from an already decoded serde_json::Value.
+ */
+pub fn parse_value(value: Value) -> Result<CoverageReport, Error> {
+    let files = match value.get("coverage") {
+        Some(Value::Object(files)) => files,
+        _ => {
+            return Err(Error::FormatError(
+                "codecov json must have a \"coverage\" object".to_string(),
+            ))
+        }
+    };
+    let mut report = CoverageReport::default();
+    for (name, lines) in files {
+        report.files.insert(name.clone(), parse_file(name, lines)?);
+    }
+    Ok(report)
+}
+
+fn parse_file(name: &str, lines: &Value) -> Result<FileCoverage, Error> {
+    let mut file = FileCoverage::default();
+    match lines {
+        Value::Object(lines) => {
+            for (line, value) in lines {
+                let line = match line.parse::<u32>() {
+                    Ok(line) => line,
+                    Err(_) => {
+                        return Err(Error::FormatError(format!(
+                            "invalid line number {:?} in {}",
+                            line, name
+                        )))
+                    }
+                };
+                parse_line(name, line, value, &mut file)?;
+            }
+        }
+        Value::Array(lines) => {
+            for (line, value) in lines.iter().enumerate() {
+                // Index 0 is a placeholder since lines are 1-based.
+                if line == 0 {
+                    continue;
+                }
+                let line = match u32::try_from(line) {
+                    Ok(line) => line,
+                    Err(_) => {
+                        return Err(Error::FormatError(format!("too many lines in {}", name)))
+                    }
+                };
+                parse_line(name, line, value, &mut file)?;
+            }
+        }
+        _ => {
+            return Err(Error::FormatError(format!(
+                "lines of {} must be an object or an array",
+                name
+            )))
+        }
+    }
+    Ok(file)
+}
+
+fn parse_line(name: &str, line: u32, value: &Value, file: &mut FileCoverage) -> Result<(), Error> {
+    match decode_line(value) {
+        Ok(Some((coverage, branches))) => {
+            file.branches += branches as usize;
+            file.lines.insert(line, coverage);
+            Ok(())
+        }
+        Ok(None) => Ok(()),
+        Err(message) => Err(Error::FormatError(format!(
+            "{} at {}:{}",
+            message, name, line
+        ))),
+    }
+}
+
+/**
+ * Decodes a single line value into its coverage and number of branches.
+ * Returns None for null (not tracked) and a message for values that are not coverage.
+ */
+pub(crate) fn decode_line(value: &Value) -> Result<Option<(LineCoverage, u32)>, String> {
+    let coverage = match value {
+        Value::Null => return Ok(None),
+        Value::Bool(true) => LineCoverage::Hit(1),
+        Value::Bool(false) => LineCoverage::Miss,
+        Value::Number(n) => match n.as_u64() {
+            Some(0) => LineCoverage::Miss,
+            Some(hits) => LineCoverage::Hit(hits),
+            None => return Err(format!("invalid hit count {}", n)),
+        },
+        Value::String(s) => match parse_branches(s) {
+            Some((covered, total)) => {
+                return Ok(Some((LineCoverage::Partial { covered, total }, total)));
+            }
+            None => return Err(format!("invalid branch coverage {:?}", s)),
+        },
+        _ => return Err("invalid line coverage".to_string()),
+    };
+    Ok(Some((coverage, 0)))
+}
+
+/**
+ * Encodes a single line as a hit count or a "covered/total" string.
+ * A partial with unknown branch counts is written as "1/2", the smallest partial,
+ * and an executed line without taken branches as "1/total" since "0/total" would read as a miss.
+ */
+pub(crate) fn encode_line(coverage: &LineCoverage) -> Value {
+    match coverage {
+        LineCoverage::Hit(hits) => Value::from(*hits),
+        LineCoverage::Miss => Value::from(0),
+        LineCoverage::Partial { covered, total } => Value::from(format!("{}/{}", covered, total)),
+        LineCoverage::PartialUnknown => Value::from("1/2"),
+        LineCoverage::PartialUntaken { total } => Value::from(format!("1/{}", (*total).max(2))),
+    }
+}
+
+/**
+ * Parses "covered/total" into a pair of numbers.
+ */
+fn parse_branches(s: &str) -> Option<(u32, u32)> {
+    let (covered, total) = s.split_once('/')?;
+    let covered = covered.trim().parse::<u32>().ok()?;
+    let total = total.trim().parse::<u32>().ok()?;
+    Some((covered, total))
+}
+
+/**
+ * Converts a CoverageReport into a Codecov JSON value.
+ * Untracked lines are omitted rather than written as null.
+ */
+pub fn to_value(report: &CoverageReport) -> Value {
+    let mut files = Map::new();
+    for (name, file) in &report.files {
+        let mut lines = Map::new();
+        for (line, coverage) in &file.lines {
+            lines.insert(line.to_string(), encode_line(coverage));
+        }
+        files.insert(name.clone(), Value::Object(lines));
+    }
+    serde_json::json!({ "coverage": Value::Object(files) })
+}
+
+/**
+ * Serializes a CoverageReport into a Codecov JSON string.
+ */
+pub fn to_string(report: &CoverageReport) -> Result<String, Error> {
+    match serde_json::to_string(&to_value(report)) {
+        Ok(json) => Ok(json),
+        Err(e) => Err(Error::SerializeError(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let j = json!({
+            "coverage": {
+                "src/lib.rs": {"1": 1, "2": 0, "3": "1/2", "4": null, "5": "2/2", "6": "0/2"},
+                "src/main.rs": [null, 3, null, 0]
+            }
+        });
+        let report = parse_value(j).unwrap();
+        let lib = &report.files["src/lib.rs"];
+        assert_eq!(lib.lines[&1], LineCoverage::Hit(1));
+        assert_eq!(lib.lines[&2], LineCoverage::Miss);
+        assert_eq!(
+            lib.lines[&3],
+            LineCoverage::Partial {
+                covered: 1,
+                total: 2
+            }
+        );
+        assert!(!lib.lines.contains_key(&4));
+        assert_eq!(
+            lib.lines[&5],
+            LineCoverage::Partial {
+                covered: 2,
+                total: 2
+            }
+        );
+        assert_eq!(
+            lib.lines[&6],
+            LineCoverage::Partial {
+                covered: 0,
+                total: 2
+            }
+        );
+        assert_eq!(lib.branches, 6);
+        let totals = lib.totals();
+        assert_eq!((totals.hits, totals.misses, totals.partials), (2, 2, 1));
+
+        let main = &report.files["src/main.rs"];
+        assert_eq!(main.lines.len(), 2);
+        assert_eq!(main.lines[&1], LineCoverage::Hit(3));
+        assert_eq!(main.lines[&3], LineCoverage::Miss);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("{}").is_err());
+        assert!(parse(r#"{"coverage": {"a": {"x": 1}}}"#).is_err());
+        assert!(parse(r#"{"coverage": {"a": {"1": "1-2"}}}"#).is_err());
+        assert!(parse(r#"{"coverage": {"a": {"1": -1}}}"#).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let j = json!({
+            "coverage": {
+                "src/lib.rs": {"1": 4, "2": 0, "3": "1/3", "4": "2/2", "5": "0/2"}
+            }
+        });
+        let report = parse_value(j.clone()).unwrap();
+        assert_eq!(to_value(&report), j);
+        let s = to_string(&report).unwrap();
+        assert_eq!(parse(&s).unwrap().files, report.files);
+        assert_eq!(
+            encode_line(&LineCoverage::PartialUntaken { total: 2 }),
+            json!("1/2")
+        );
+    }
+}
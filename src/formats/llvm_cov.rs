@@ -0,0 +1,413 @@
+/**
+ * Parser for the JSON emitted by `llvm-cov export` (and `cargo llvm-cov --json`).
+ * https://llvm.org/docs/CommandGuide/llvm-cov.html#llvm-cov-export
+ */
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+use crate::coverage::{CoverageReport, FileCoverage, LineCoverage};
+use crate::errors::Error;
+use crate::merge::{merge_file, MergeStrategy};
+
+const EXPORT_TYPE: &str = "llvm.coverage.json.export";
+
+/**
+ * Export is the top level object of an llvm-cov export.
+ */
+#[derive(Deserialize, Debug)]
+struct Export {
+    #[serde(rename = "type")]
+    export_type: String,
+    data: Vec<ExportData>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExportData {
+    files: Vec<ExportFile>,
+    #[serde(default)]
+    functions: Vec<ExportFunction>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExportFile {
+    filename: String,
+    #[serde(default)]
+    segments: Vec<Segment>,
+    #[serde(default)]
+    branches: Vec<Vec<u64>>,
+}
+
+/**
+ * Function regions are [line_start, col_start, line_end, col_end, count, file_id, expanded_file_id, kind].
+ * Each instantiation of a generic function is exported as its own function with the same regions.
+ */
+#[derive(Deserialize, Debug)]
+struct ExportFunction {
+    #[serde(default)]
+    filenames: Vec<String>,
+    #[serde(default)]
+    regions: Vec<Vec<u64>>,
+}
+
+impl ExportFunction {
+    /**
+     * Returns the start line and column of the first region, which identify the function in its file.
+     */
+    fn start(&self) -> Option<(u64, u64)> {
+        let region = self.regions.first()?;
+        Some((*region.first()?, *region.get(1)?))
+    }
+}
+
+/**
+ * Segment is [line, col, count, has_count, is_region_entry, is_gap_region].
+ * is_gap_region is missing in exports older than LLVM 7.
+ * The column is not needed to compute line coverage.
+ */
+#[derive(Deserialize, Debug)]
+struct Segment(u32, IgnoredAny, u64, bool, bool, #[serde(default)] bool);
+
+impl Segment {
+    fn line(&self) -> u32 {
+        self.0
+    }
+
+    fn count(&self) -> u64 {
+        self.2
+    }
+
+    fn has_count(&self) -> bool {
+        self.3
+    }
+
+    fn is_start_of_region(&self) -> bool {
+        self.3 && self.4 && !self.5
+    }
+
+    fn is_start_of_skipped_region(&self) -> bool {
+        !self.3 && self.4
+    }
+}
+
+/**
+ * Branch records are [line_start, col_start, line_end, col_end, true_count, false_count, ...].
+ * Each record stands for two branches (taken and not taken).
+ */
+fn branch_counts(branch: &[u64]) -> Option<(u32, u32)> {
+    let line = u32::try_from(*branch.first()?).ok()?;
+    let true_count = *branch.get(4)?;
+    let false_count = *branch.get(5)?;
+    let covered = u32::from(true_count > 0) + u32::from(false_count > 0);
+    Some((line, covered))
+}
+
+/**
+ * Parses an llvm-cov export JSON string into a CoverageReport.
+ */
+pub fn parse(json: &str) -> Result<CoverageReport, Error> {
+    let export = match serde_json::from_str::<Export>(json) {
+        Ok(export) => export,
+        Err(e) => return Err(Error::DeserializeError(e)),
+    };
+    from_export(export)
+}
+
+/**
+ * Parses an llvm-cov export from an already decoded serde_json::Value.
+ */
+pub fn parse_value(value: serde_json::Value) -> Result<CoverageReport, Error> {
+    let export = match serde_json::from_value::<Export>(value) {
+        Ok(export) => export,
+        Err(e) => return Err(Error::DeserializeError(e)),
+    };
+    from_export(export)
+}
+
+fn from_export(export: Export) -> Result<CoverageReport, Error> {
+    if export.export_type != EXPORT_TYPE {
+        return Err(Error::FormatError(format!(
+            "unexpected llvm-cov export type: {}",
+            export.export_type
+        )));
+    }
+    let mut report = CoverageReport::default();
+    let mut seen = BTreeSet::new();
+    for data in &export.data {
+        for file in &data.files {
+            let coverage = file_coverage(file);
+            report
+                .files
+                .entry(file.filename.clone())
+                .and_modify(|existing| merge_file(existing, &coverage, MergeStrategy::Max, 0))
+                .or_insert(coverage);
+        }
+        for function in &data.functions {
+            let Some(name) = function.filenames.first() else {
+                continue;
+            };
+            if let Some((line, col)) = function.start() {
+                if !seen.insert((name, line, col)) {
+                    continue;
+                }
+            }
+            if let Some(file) = report.files.get_mut(name) {
+                file.methods += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/**
+ * Computes the line coverage of a single file from its segments and branches.
+ * Line counts follow llvm-cov's own LineCoverageStats algorithm.
+ * A line is partial when some of the regions starting on it were never executed
+ * or when some of its branches were never taken.
+ * Lines with branches keep their branch counts, whether all, some or none of them were taken;
+ * an executed line none of whose branches were taken is PartialUntaken rather than a miss.
+ */
+fn file_coverage(file: &ExportFile) -> FileCoverage {
+    let mut lines = BTreeMap::new();
+
+    let mut by_line: BTreeMap<u32, Vec<&Segment>> = BTreeMap::new();
+    for segment in &file.segments {
+        by_line.entry(segment.line()).or_default().push(segment);
+    }
+    let first = by_line.keys().next().copied();
+    let last = by_line.keys().next_back().copied();
+    let mut wrapped: Option<&Segment> = None;
+    if let (Some(first), Some(last)) = (first, last) {
+        for line in first..=last {
+            let segments = by_line.get(&line).map(Vec::as_slice).unwrap_or(&[]);
+            if let Some(coverage) = line_coverage(segments, wrapped) {
+                lines.insert(line, coverage);
+            }
+            if let Some(segment) = segments.last() {
+                wrapped = Some(segment);
+            }
+        }
+    }
+
+    let mut branches: BTreeMap<u32, (u32, u32)> = BTreeMap::new();
+    for branch in &file.branches {
+        if let Some((line, covered)) = branch_counts(branch) {
+            let entry = branches.entry(line).or_insert((0, 0));
+            entry.0 += covered;
+            entry.1 += 2;
+        }
+    }
+    let mut total_branches = 0;
+    for (line, (covered, total)) in branches {
+        total_branches += total as usize;
+        if let Some(coverage) = lines.get_mut(&line) {
+            *coverage = match coverage {
+                LineCoverage::Miss => LineCoverage::Partial { covered: 0, total },
+                _ if covered == 0 => LineCoverage::PartialUntaken { total },
+                _ => LineCoverage::Partial { covered, total },
+            };
+        }
+    }
+
+    FileCoverage {
+        lines,
+        branches: total_branches,
+        ..FileCoverage::default()
+    }
+}
+
+fn line_coverage(segments: &[&Segment], wrapped: Option<&Segment>) -> Option<LineCoverage> {
+    let starts: Vec<&&Segment> = segments.iter().filter(|s| s.is_start_of_region()).collect();
+    let skipped = segments
+        .first()
+        .map(|s| s.is_start_of_skipped_region())
+        .unwrap_or(false);
+    let wrapped_has_count = wrapped.map(Segment::has_count).unwrap_or(false);
+    if skipped || (!wrapped_has_count && starts.is_empty()) {
+        return None;
+    }
+
+    let mut count = wrapped.map(Segment::count).unwrap_or(0);
+    for segment in &starts {
+        count = count.max(segment.count());
+    }
+    if count == 0 {
+        return Some(LineCoverage::Miss);
+    }
+
+    let mut covered = 0;
+    let mut total = 0;
+    if wrapped_has_count {
+        total += 1;
+        covered += u32::from(wrapped.map(Segment::count).unwrap_or(0) > 0);
+    }
+    for segment in &starts {
+        total += 1;
+        covered += u32::from(segment.count() > 0);
+    }
+    if covered < total {
+        Some(LineCoverage::Partial { covered, total })
+    } else {
+        Some(LineCoverage::Hit(count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let j = json!({
+            "type": "llvm.coverage.json.export",
+            "version": "2.0.1",
+            "data": [{
+                "files": [{
+                    "filename": "/app/src/lib.rs",
+                    "segments": [
+                        [1, 1, 5, true, true, false],
+                        [2, 5, 0, true, true, false],
+                        [2, 10, 0, false, false, false],
+                        [3, 1, 0, true, true, false],
+                        [3, 5, 0, false, false, false],
+                        [6, 1, 2, true, true, false],
+                        [7, 1, 0, false, true, false],
+                        [8, 1, 0, false, false, false]
+                    ],
+                    "branches": [
+                        [6, 4, 6, 8, 2, 0, 0, 0, 4]
+                    ],
+                    "expansions": [],
+                    "summary": {}
+                }],
+                "functions": [
+                    {"name": "a", "count": 5, "regions": [[1, 1, 3, 2, 5, 0, 0, 0]], "filenames": ["/app/src/lib.rs"]},
+                    {"name": "b", "count": 0, "regions": [[6, 1, 7, 2, 2, 0, 0, 0]], "filenames": ["/app/src/lib.rs"]}
+                ],
+                "totals": {}
+            }]
+        });
+        let report = parse_value(j).unwrap();
+        let file = &report.files["/app/src/lib.rs"];
+        assert_eq!(file.lines[&1], LineCoverage::Hit(5));
+        assert_eq!(
+            file.lines[&2],
+            LineCoverage::Partial {
+                covered: 1,
+                total: 2
+            }
+        );
+        assert_eq!(file.lines[&3], LineCoverage::Miss);
+        assert!(!file.lines.contains_key(&4));
+        assert!(!file.lines.contains_key(&5));
+        assert_eq!(
+            file.lines[&6],
+            LineCoverage::Partial {
+                covered: 1,
+                total: 2
+            }
+        );
+        assert!(!file.lines.contains_key(&7));
+        assert_eq!(file.branches, 2);
+        assert_eq!(file.methods, 2);
+
+        let totals = report.totals();
+        assert_eq!(totals.hits, 1);
+        assert_eq!(totals.misses, 1);
+        assert_eq!(totals.partials, 2);
+    }
+
+    #[test]
+    fn test_branches() {
+        let j = json!({
+            "type": "llvm.coverage.json.export",
+            "version": "2.0.1",
+            "data": [{
+                "files": [{
+                    "filename": "/app/src/lib.rs",
+                    "segments": [
+                        [1, 1, 4, true, true, false],
+                        [3, 10, 0, false, false, false],
+                        [4, 1, 0, true, true, false],
+                        [4, 10, 0, false, false, false]
+                    ],
+                    "branches": [
+                        [1, 4, 1, 8, 3, 1, 0, 0, 4],
+                        [2, 4, 2, 8, 0, 0, 0, 0, 4],
+                        [3, 4, 3, 8, 4, 0, 0, 0, 4],
+                        [4, 4, 4, 8, 0, 0, 0, 0, 4]
+                    ]
+                }]
+            }]
+        });
+        let report = parse_value(j).unwrap();
+        let file = &report.files["/app/src/lib.rs"];
+        assert_eq!(
+            file.lines[&1],
+            LineCoverage::Partial {
+                covered: 2,
+                total: 2
+            }
+        );
+        // Line 2 was executed but its condition was never evaluated.
+        assert_eq!(file.lines[&2], LineCoverage::PartialUntaken { total: 2 });
+        assert!(file.lines[&2].is_covered());
+        assert_eq!(
+            file.lines[&3],
+            LineCoverage::Partial {
+                covered: 1,
+                total: 2
+            }
+        );
+        assert_eq!(
+            file.lines[&4],
+            LineCoverage::Partial {
+                covered: 0,
+                total: 2
+            }
+        );
+        assert_eq!(file.branches, 8);
+
+        let totals = report.totals();
+        assert_eq!(totals.hits, 1);
+        assert_eq!(totals.partials, 2);
+        assert_eq!(totals.misses, 1);
+    }
+
+    #[test]
+    fn test_generic_instantiations() {
+        let j = json!({
+            "type": "llvm.coverage.json.export",
+            "version": "2.0.1",
+            "data": [{
+                "files": [{
+                    "filename": "/app/src/lib.rs",
+                    "segments": [
+                        [1, 1, 3, true, true, false],
+                        [3, 2, 0, false, false, false],
+                        [5, 1, 1, true, true, false],
+                        [6, 2, 0, false, false, false]
+                    ]
+                }],
+                "functions": [
+                    {"name": "_RINvCs_3lib8identitylEB2_", "count": 2, "regions": [[1, 1, 3, 2, 2, 0, 0, 0]], "filenames": ["/app/src/lib.rs"]},
+                    {"name": "_RINvCs_3lib8identityhEB2_", "count": 1, "regions": [[1, 1, 3, 2, 1, 0, 0, 0]], "filenames": ["/app/src/lib.rs"]},
+                    {"name": "_RNvCs_3lib4main", "count": 1, "regions": [[5, 1, 6, 2, 1, 0, 0, 0]], "filenames": ["/app/src/lib.rs"]}
+                ]
+            }]
+        });
+        let report = parse_value(j).unwrap();
+        let file = &report.files["/app/src/lib.rs"];
+        assert_eq!(file.methods, 2);
+        assert_eq!(file.lines[&1], LineCoverage::Hit(3));
+    }
+
+    #[test]
+    fn test_parse_unexpected_type() {
+        let j = json!({"type": "something.else", "data": []});
+        assert!(matches!(parse_value(j), Err(Error::FormatError(_))));
+    }
+}
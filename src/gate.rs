@@ -0,0 +1,310 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::api::CodecovApi;
+use crate::author::Author;
+use crate::branch_detail::Report;
+use crate::errors::Error;
+use crate::path_fixer;
+use crate::revision::Revision;
+use crate::totals::Totals;
+use crate::tree::CoverageTree;
+use crate::Client;
+
+/**
+ * Gate is a set of rules that decide whether coverage is good enough, e.g. to fail a CI step.
+ *
+ * ```
+ * use codecov::gate::Gate;
+ *
+ * let gate = Gate::new()
+ *     .with_target(80.0)
+ *     .with_max_drop(1.0)
+ *     .with_path_minimum("src/formats", 70.0);
+ * ```
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Gate {
+    pub target: Option<f64>,
+    pub max_drop: Option<f64>,
+    pub path_minimums: Vec<PathMinimum>,
+}
+
+/**
+ * PathMinimum is the minimum coverage of the files under a path.
+ * path is matched like codecov.yml paths: a directory, a file or a glob.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathMinimum {
+    pub path: String,
+    pub minimum: f64,
+}
+
+/**
+ * Rule is the rule of a Gate that a GateFailure comes from.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "rule", content = "path")]
+pub enum Rule {
+    Target,
+    MaxDrop,
+    PathMinimum(String),
+}
+
+/**
+ * GateFailure is a struct that represents a failed rule.
+ * actual is the measured value (coverage or drop) and expected the limit it violated.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GateFailure {
+    pub rule: Rule,
+    pub actual: f64,
+    pub expected: f64,
+    pub message: String,
+}
+
+/**
+ * GateResult is a struct that represents the outcome of a Gate.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GateResult {
+    pub head: Totals,
+    pub base: Option<Totals>,
+    pub failures: Vec<GateFailure>,
+}
+
+impl Gate {
+    pub fn new() -> Gate {
+        Gate::default()
+    }
+
+    /**
+     * Fails when the head coverage is below target.
+     */
+    pub fn with_target(mut self, target: f64) -> Gate {
+        self.target = Some(target);
+        self
+    }
+
+    /**
+     * Fails when the head coverage is lower than the base coverage by more than max_drop points.
+     */
+    pub fn with_max_drop(mut self, max_drop: f64) -> Gate {
+        self.max_drop = Some(max_drop);
+        self
+    }
+
+    /**
+     * Fails when the coverage of the files under path is below minimum,
+     * or when no file matches path.
+     */
+    pub fn with_path_minimum(mut self, path: &str, minimum: f64) -> Gate {
+        self.path_minimums.push(PathMinimum {
+            path: path.to_string(),
+            minimum,
+        });
+        self
+    }
+
+    /**
+     * Applies the rules to a head report and optionally the totals of a base.
+     * The drop rule is skipped without a base.
+     */
+    pub fn check(&self, head: &Report, base: Option<&Totals>) -> GateResult {
+        let mut failures = vec![];
+        let coverage = head.totals.coverage;
+        if let Some(target) = self.target {
+            if coverage < target {
+                failures.push(GateFailure {
+                    rule: Rule::Target,
+                    actual: coverage,
+                    expected: target,
+                    message: format!(
+                        "coverage {:.2}% is below the target {:.2}%",
+                        coverage, target
+                    ),
+                });
+            }
+        }
+        if let (Some(max_drop), Some(base)) = (self.max_drop, base) {
+            let drop = base.coverage - coverage;
+            if drop > max_drop {
+                failures.push(GateFailure {
+                    rule: Rule::MaxDrop,
+                    actual: drop,
+                    expected: max_drop,
+                    message: format!(
+                        "coverage dropped by {:.2}% ({:.2}% -> {:.2}%), more than the allowed {:.2}%",
+                        drop, base.coverage, coverage, max_drop
+                    ),
+                });
+            }
+        }
+        for minimum in &self.path_minimums {
+            let tree = CoverageTree::from_files(
+                head.files
+                    .iter()
+                    .filter(|file| path_fixer::path_matches(&minimum.path, &file.name))
+                    .map(|file| (file.name.as_str(), &file.totals)),
+            );
+            let rule = Rule::PathMinimum(minimum.path.clone());
+            let tree = match tree {
+                Ok(tree) => tree,
+                Err(e) => {
+                    failures.push(GateFailure {
+                        rule,
+                        actual: 0.0,
+                        expected: minimum.minimum,
+                        message: format!("cannot aggregate {}: {}", minimum.path, e),
+                    });
+                    continue;
+                }
+            };
+            if tree.totals.files == 0 {
+                failures.push(GateFailure {
+                    rule,
+                    actual: 0.0,
+                    expected: minimum.minimum,
+                    message: format!("no files match {}", minimum.path),
+                });
+            } else if tree.totals.coverage < minimum.minimum {
+                failures.push(GateFailure {
+                    rule,
+                    actual: tree.totals.coverage,
+                    expected: minimum.minimum,
+                    message: format!(
+                        "coverage of {} {:.2}% is below the minimum {:.2}%",
+                        minimum.path, tree.totals.coverage, minimum.minimum
+                    ),
+                });
+            }
+        }
+        GateResult {
+            head: head.totals.clone(),
+            base: base.cloned(),
+            failures,
+        }
+    }
+}
+
+impl GateResult {
+    /**
+     * Returns true if no rule failed.
+     */
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for GateFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl fmt::Display for GateResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.passed() {
+            return write!(f, "passed: coverage {:.2}%", self.head.coverage);
+        }
+        write!(f, "failed: coverage {:.2}%", self.head.coverage)?;
+        for failure in &self.failures {
+            write!(f, "\n  - {}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+impl Client {
+    /**
+     * Fetches the head report and the base totals, then applies the gate.
+     */
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(repo = %author, head = %head))
+    )]
+    pub fn check_gate(
+        &self,
+        gate: &Gate,
+        author: &Author,
+        head: &Revision,
+        base: Option<&Revision>,
+    ) -> Result<GateResult, Error> {
+        CodecovApi::check_gate(self, gate, author, head, base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn report() -> Report {
+        serde_json::from_value(json!({
+            "files": [
+                {"name": "src/lib.rs", "totals": {"hits": 8, "misses": 2, "coverage": 80.0}},
+                {"name": "src/formats/llvm_cov.rs", "totals": {"hits": 1, "misses": 1, "coverage": 50.0}},
+                {"name": "src/formats/codecov_json.rs", "totals": {"hits": 2, "misses": 0, "coverage": 100.0}}
+            ],
+            "totals": {"hits": 11, "misses": 3, "coverage": 78.57}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_passes() {
+        let base = Totals {
+            coverage: 79.0,
+            ..Default::default()
+        };
+        let result = Gate::new()
+            .with_target(75.0)
+            .with_max_drop(1.0)
+            .with_path_minimum("src/formats", 70.0)
+            .check(&report(), Some(&base));
+        assert!(result.passed());
+        assert_eq!(result.to_string(), "passed: coverage 78.57%");
+    }
+
+    #[test]
+    fn test_check_fails() {
+        let base = Totals {
+            coverage: 85.0,
+            ..Default::default()
+        };
+        let result = Gate::new()
+            .with_target(80.0)
+            .with_max_drop(1.0)
+            .with_path_minimum("src/formats/", 80.0)
+            .with_path_minimum("src/*.rs", 80.0)
+            .with_path_minimum("tests", 50.0)
+            .check(&report(), Some(&base));
+        let rules: Vec<&Rule> = result.failures.iter().map(|f| &f.rule).collect();
+        assert_eq!(
+            rules,
+            vec![
+                &Rule::Target,
+                &Rule::MaxDrop,
+                &Rule::PathMinimum("src/formats/".to_string()),
+                &Rule::PathMinimum("tests".to_string()),
+            ]
+        );
+        assert_eq!(
+            result.to_string(),
+            "failed: coverage 78.57%\n  \
+             - coverage 78.57% is below the target 80.00%\n  \
+             - coverage dropped by 6.43% (85.00% -> 78.57%), more than the allowed 1.00%\n  \
+             - coverage of src/formats/ 75.00% is below the minimum 80.00%\n  \
+             - no files match tests"
+        );
+    }
+
+    #[test]
+    fn test_check_without_base() {
+        let result = Gate::new().with_max_drop(0.0).check(&report(), None);
+        assert!(result.passed());
+        assert_eq!(result.base, None);
+    }
+}
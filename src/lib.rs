@@ -1,23 +1,48 @@
+pub mod api;
+#[cfg(feature = "async")]
+pub mod async_client;
 pub mod author;
 pub mod branch_detail;
 pub mod branches;
+pub mod cache;
 pub mod commits;
+pub mod config;
+pub mod coverage;
+pub mod diff;
+pub mod endpoint;
 pub mod errors;
+pub mod formats;
+pub mod gate;
+pub mod merge;
+pub mod metrics;
+pub mod org;
 pub mod owner;
+pub mod path_fixer;
+pub mod replay;
 pub mod repos;
+pub mod revision;
+pub mod service;
+pub mod status;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod timestamp;
 pub mod totals;
+pub mod tree;
+pub mod uploads;
 pub mod url;
+pub mod wait;
 use author::Author;
+use url::Url;
 
 use crate::errors::Error;
 
 /**
-```
-use codecov::{Client, owner::Owner};
+```no_run
+use codecov::{Client, owner::Owner, service::Service};
 
 // let client = Client::new("1234-5678-9012-3456"); // Set token directly
 let client = Client::new_from_env().unwrap();  // Read CODECOV_OWNER_TOKEN from environment variable
-let owner = Owner::new("github", "kitsuyui");
+let owner = Owner::new(Service::Github, "kitsuyui");
 let repos = client.get_all_repos(&owner).unwrap();
 println!("{:?}", repos.len());
 
@@ -27,12 +52,18 @@ println!("{:?}", repo_detail);
 println!("{}", repo_detail.latest_coverage());
 ```
  */
-
 /**
  * Client is a struct that represents a client to the Codecov API.
+ * Clones share the recording, replay and cache of the original.
  */
+#[derive(Clone)]
 pub struct Client {
     token: String,
+    api_endpoint: String,
+    transport: replay::Transport,
+    cache: Option<cache::Cache>,
+    cache_mode: cache::CacheMode,
+    metrics: Option<std::sync::Arc<dyn metrics::Metrics>>,
 }
 
 impl Client {
@@ -45,21 +76,63 @@ impl Client {
     }
 
     pub fn new(token: String) -> Client {
-        Client { token }
+        Client {
+            token,
+            api_endpoint: url::API_ENDPOINT.to_string(),
+            transport: replay::Transport::Http,
+            cache: None,
+            cache_mode: cache::CacheMode::Use,
+            metrics: None,
+        }
+    }
+
+    /**
+     * Returns the client that saves every request and response to a fixture file at path.
+     * The Authorization header is replaced with replay::REDACTED.
+     */
+    pub fn with_recording(mut self, path: &std::path::Path) -> Client {
+        self.transport =
+            replay::Transport::Record(std::sync::Arc::new(replay::Recorder::new(path)));
+        self
+    }
+
+    /**
+     * Returns the client that serves the responses of a fixture file instead of sending requests.
+     * Requests that were not recorded fail with Error::ReplayError.
+     */
+    pub fn with_replay(mut self, path: &std::path::Path) -> Result<Client, Error> {
+        let fixture = replay::Fixture::load(path)?;
+        self.transport =
+            replay::Transport::Replay(std::sync::Arc::new(replay::Replayer::new(fixture)));
+        Ok(self)
+    }
+
+    /**
+     * Returns the client with another base URL of the API, e.g. a self-hosted Codecov
+     * or testing::FakeServer. Defaults to url::API_ENDPOINT.
+     */
+    pub fn with_api_endpoint(mut self, api_endpoint: &str) -> Client {
+        self.api_endpoint = api_endpoint.to_string();
+        self
     }
 
     fn auth_header_val(&self) -> String {
         format!("bearer {}", self.token)
     }
 
-    fn owner_endpoint(&self, owner: &owner::Owner) -> String {
-        let api_endpoint = "https://codecov.io/api/v2";
-        format!("{}/{}/{}", api_endpoint, owner.service, owner.username)
+    fn owner_endpoint(&self, owner: &owner::Owner) -> Result<Url, Error> {
+        let api_endpoint = match Url::parse(&self.api_endpoint) {
+            Ok(api_endpoint) => api_endpoint,
+            Err(e) => return Err(Error::UrlError(e)),
+        };
+        url::join_segments(&api_endpoint, &[owner.service.as_str(), &owner.username])
     }
 
-    fn repos_endpoint(&self, author: &author::Author) -> String {
-        let owner_endpoint = self.owner_endpoint(&author.to_owner());
-        format!("{}/repos/{}", owner_endpoint, author.name)
+    fn repos_endpoint(&self, author: &author::Author) -> Result<Url, Error> {
+        url::join_segments(
+            &self.owner_endpoint(&author.to_owner())?,
+            &["repos", &author.name],
+        )
     }
 
     /**
@@ -67,9 +140,14 @@ impl Client {
      * /repos endpoint returns a list of repos for a given owner with pagination.
      * This function will make multiple requests to get all repos.
      */
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(owner = %owner))
+    )]
     pub fn get_all_repos(&self, owner: &owner::Owner) -> Result<Vec<repos::Repo>, Error> {
         let mut repos = Vec::new();
-        let mut url = format!("{}/repos?page_size=100", self.owner_endpoint(owner));
+        let mut url = url::join_segments(&self.owner_endpoint(owner)?, &["repos"])?;
+        url.query_pairs_mut().append_pair("page_size", "100");
         loop {
             let mut repo_list = self.get_repos_page(&url)?;
             match &mut repo_list {
@@ -79,7 +157,7 @@ impl Client {
                     ..
                 } => {
                     repos.append(results);
-                    url = next_url.to_string();
+                    url = next_url.clone();
                     continue;
                 }
                 repos::ReposAPIResponse {
@@ -99,27 +177,147 @@ impl Client {
      * get_repos_page returns a single page of repos.
      * This is a helper function for get_all_repos.
      */
-    fn get_repos_page(&self, url: &str) -> Result<repos::ReposAPIResponse, Error> {
+    fn get_repos_page(&self, url: &Url) -> Result<repos::ReposAPIResponse, Error> {
         self.api_request::<repos::ReposAPIResponse>(url)
     }
 
     /**
      * api_raw_json returns a serde_json::Value from a given url.
+     * Error statuses other than 404 are an Error::HttpError; 404 bodies are returned for the caller to report.
+     */
+    fn api_raw_json(&self, url: &Url) -> Result<serde_json::Value, Error> {
+        let (status, body) = match &self.transport {
+            replay::Transport::Http => match &self.cache {
+                Some(cache) if self.cache_mode != cache::CacheMode::Bypass => {
+                    self.cached_get(cache, url)?
+                }
+                _ => {
+                    let (status, _, body) = self.http_get(url, None)?;
+                    (status, body)
+                }
+            },
+            replay::Transport::Record(recorder) => {
+                let (status, _, body) = self.http_get(url, None)?;
+                let path = replay::relative_path(&self.api_endpoint, url);
+                recorder.record("GET", &path, status, &body)?;
+                (status, body)
+            }
+            replay::Transport::Replay(replayer) => {
+                replayer.replay("GET", &replay::relative_path(&self.api_endpoint, url))?
+            }
+        };
+        check_status(status, body)
+    }
+
+    /**
+     * http_get sends a GET request and returns the status, the validators and the JSON body.
+     * With validators, the request is conditional and a 304 response has a null body.
+     * The request is reported to the metrics hook and, with the `tracing` feature, traced in a span.
+     */
+    fn http_get(
+        &self,
+        url: &Url,
+        validators: Option<&cache::Validators>,
+    ) -> Result<(u16, cache::Validators, serde_json::Value), Error> {
+        let endpoint = self.endpoint(url);
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "codecov.request",
+            endpoint = endpoint.as_str(),
+            path = url.path(),
+            page = tracing::field::Empty,
+            status = tracing::field::Empty,
+            bytes = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+            error = tracing::field::Empty,
+        );
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        let started = std::time::Instant::now();
+        let response = self.send_get(url, validators);
+        let request = metrics::RequestMetrics {
+            endpoint,
+            status: response.as_ref().ok().map(|(status, _, _)| *status),
+            bytes: response.as_ref().map_or(0, |(_, _, body)| body.len()),
+            latency: started.elapsed(),
+        };
+        #[cfg(feature = "tracing")]
+        {
+            if let Some((_, page)) = url.query_pairs().find(|(key, _)| key == "page") {
+                span.record("page", page.as_ref());
+            }
+            if let Some(status) = request.status {
+                span.record("status", status);
+            }
+            span.record("bytes", request.bytes);
+            span.record("latency_ms", request.latency.as_millis() as u64);
+            if let Err(e) = &response {
+                span.record("error", tracing::field::debug(e));
+            }
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.record(&request);
+        }
+        let (status, validators, body) = response?;
+        if status == 304 {
+            return Ok((status, validators, serde_json::Value::Null));
+        }
+        match serde_json::from_slice::<serde_json::Value>(&body) {
+            Ok(body) => Ok((status, validators, body)),
+            Err(e) => Err(Error::DeserializeError(e)),
+        }
+    }
+
+    /**
+     * send_get sends a GET request and returns the status, the validators and the raw body.
      */
-    fn api_raw_json(&self, url: &str) -> Result<serde_json::Value, Error> {
+    fn send_get(
+        &self,
+        url: &Url,
+        validators: Option<&cache::Validators>,
+    ) -> Result<(u16, cache::Validators, Vec<u8>), Error> {
         let client = reqwest::blocking::Client::new();
-        let req = client
-            .get(url)
+        let mut req = client
+            .get(url.clone())
             .header("Authorization", self.auth_header_val());
+        if let Some(validators) = validators {
+            if let Some(etag) = &validators.etag {
+                req = req.header("If-None-Match", etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                req = req.header("If-Modified-Since", last_modified);
+            }
+        }
         let res = match req.send() {
             Ok(res) => res,
             Err(e) => return Err(Error::ReqwestError(e)),
         };
-        let res = match res.json::<serde_json::Value>() {
-            Ok(res) => res,
+        let status = res.status().as_u16();
+        let header = |name: &str| {
+            res.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+        let validators = cache::Validators {
+            etag: header("ETag"),
+            last_modified: header("Last-Modified"),
+        };
+        let body = match res.bytes() {
+            Ok(body) => body.to_vec(),
             Err(e) => return Err(Error::ReqwestError(e)),
         };
-        Ok(res)
+        Ok((status, validators, body))
+    }
+
+    /**
+     * Returns the endpoint a URL of this client points to.
+     */
+    fn endpoint(&self, url: &Url) -> endpoint::Endpoint {
+        match Url::parse(&self.api_endpoint) {
+            Ok(api_endpoint) => endpoint::Endpoint::classify(&api_endpoint, url),
+            Err(_) => endpoint::Endpoint::Other,
+        }
     }
 
     /**
@@ -127,7 +325,7 @@ impl Client {
      */
     fn api_request<T: serde::de::DeserializeOwned + std::fmt::Debug>(
         &self,
-        url: &str,
+        url: &Url,
     ) -> Result<T, Error> {
         let res = self.api_raw_json(url)?;
         let data = match serde_json::from_value::<T>(res) {
@@ -141,24 +339,144 @@ impl Client {
      * get_commits returns a list of commits for a given author.
      * https://docs.codecov.com/reference/repos_commits_list
      */
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(repo = %author))
+    )]
     pub fn get_commits(
         &self,
         author: &author::Author,
     ) -> Result<commits::CommitsAPIResponse, Error> {
-        let url = format!("{}/commits", self.repos_endpoint(author));
+        let url = url::join_segments(&self.repos_endpoint(author)?, &["commits"])?;
         let commits = self.api_request::<commits::CommitsAPIResponse>(&url)?;
         Ok(commits)
     }
 
+    /**
+     * get_commit_history returns all commits for a given author, following the pagination of /commits.
+     * https://docs.codecov.com/reference/repos_commits_list
+     */
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(repo = %author))
+    )]
+    pub fn get_commit_history(
+        &self,
+        author: &author::Author,
+    ) -> Result<commits::CommitHistory, Error> {
+        let mut commits = Vec::new();
+        let mut url = url::join_segments(&self.repos_endpoint(author)?, &["commits"])?;
+        url.query_pairs_mut().append_pair("page_size", "100");
+        loop {
+            let mut page = self.api_request::<commits::CommitsAPIResponse>(&url)?;
+            commits.append(&mut page.results);
+            match page.next {
+                Some(next_url) => url = next_url,
+                None => break,
+            }
+        }
+        Ok(commits::CommitHistory::new(commits))
+    }
+
+    /**
+     * get_commit returns a single commit for a given author and commit sha.
+     * https://docs.codecov.com/reference/repos_commits_retrieve
+     */
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(repo = %author, commitid = commitid))
+    )]
+    pub fn get_commit(
+        &self,
+        author: &author::Author,
+        commitid: &str,
+    ) -> Result<commits::Commit, Error> {
+        let url = url::join_segments(&self.repos_endpoint(author)?, &["commits", commitid])?;
+        let res = self.api_raw_json(&url)?;
+        if res.get("commitid").is_none() {
+            if let Some(serde_json::Value::String(detail)) = res.get("detail") {
+                return Err(Error::NotFoundError(detail.to_string()));
+            }
+        }
+        match serde_json::from_value::<commits::Commit>(res) {
+            Ok(commit) => Ok(commit),
+            Err(e) => Err(Error::DeserializeError(e)),
+        }
+    }
+
+    /**
+     * get_commit_uploads returns all uploads (sessions) received for a given commit.
+     * https://docs.codecov.com/reference/repos_commits_uploads_list
+     */
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(repo = %author, commitid = commitid))
+    )]
+    pub fn get_commit_uploads(
+        &self,
+        author: &author::Author,
+        commitid: &str,
+    ) -> Result<Vec<uploads::Upload>, Error> {
+        let mut uploads = Vec::new();
+        let mut url = url::join_segments(
+            &self.repos_endpoint(author)?,
+            &["commits", commitid, "uploads"],
+        )?;
+        url.query_pairs_mut().append_pair("page_size", "100");
+        loop {
+            let mut page = self.api_request::<uploads::UploadsAPIResponse>(&url)?;
+            uploads.append(&mut page.results);
+            match page.next {
+                Some(next_url) => url = next_url,
+                None => break,
+            }
+        }
+        Ok(uploads)
+    }
+
+    /**
+     * get_totals returns the totals of the head commit of a branch, or of a given commit.
+     */
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(repo = %author, revision = %revision))
+    )]
+    pub fn get_totals(
+        &self,
+        author: &author::Author,
+        revision: &revision::Revision,
+    ) -> Result<totals::Totals, Error> {
+        api::CodecovApi::get_totals(self, author, revision)
+    }
+
+    /**
+     * get_report returns the report (totals and file totals) of the head commit of a branch, or of a given commit.
+     */
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(repo = %author, revision = %revision))
+    )]
+    pub fn get_report(
+        &self,
+        author: &author::Author,
+        revision: &revision::Revision,
+    ) -> Result<branch_detail::Report, Error> {
+        api::CodecovApi::get_report(self, author, revision)
+    }
+
     /**
      * get_branches returns a list of branches for a given author.
      * https://docs.codecov.com/reference/repos_branches_list
      */
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(repo = %author))
+    )]
     pub fn get_branches(
         &self,
         author: &author::Author,
     ) -> Result<branches::BranchesAPIResponse, Error> {
-        let url = format!("{}/branches", self.repos_endpoint(author));
+        let url = url::join_segments(&self.repos_endpoint(author)?, &["branches"])?;
         let branches = self.api_request::<branches::BranchesAPIResponse>(&url)?;
         Ok(branches)
     }
@@ -167,55 +485,143 @@ impl Client {
      * get_branch_detail returns a branch detail for a given author and branch name.
      * https://docs.codecov.com/reference/repos_branches_retrieve
      */
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(repo = %author, branch = branch_name))
+    )]
     pub fn get_branch_detail(
         &self,
         author: &Author,
         branch_name: &str,
     ) -> Result<branch_detail::BranchDetailAPIResponse, Error> {
-        let url = format!("{}/branches/{}", self.repos_endpoint(author), branch_name);
+        let url = url::join_segments(&self.repos_endpoint(author)?, &["branches", branch_name])?;
         let branch_detail = self.api_request::<branch_detail::BranchDetailAPIResponse>(&url)?;
         Ok(branch_detail)
     }
 }
 
+/**
+ * Returns the body of a successful or 404 response, and Error::HttpError for other error statuses.
+ */
+pub(crate) fn check_status(
+    status: u16,
+    body: serde_json::Value,
+) -> Result<serde_json::Value, Error> {
+    if (200..300).contains(&status) || status == 404 {
+        return Ok(body);
+    }
+    let detail = match body.get("detail") {
+        Some(serde_json::Value::String(detail)) => detail.to_string(),
+        _ => body.to_string(),
+    };
+    Err(Error::HttpError { status, detail })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_endpoints() {
+        let client = Client::new("token".to_string());
+        let author: Author = "github/kitsuyui/rust-codecov".parse().unwrap();
+        let url = client.repos_endpoint(&author).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://codecov.io/api/v2/github/kitsuyui/repos/rust-codecov"
+        );
+        let url = url::join_segments(&url, &["branches", "feature/foo"]).unwrap();
+        assert!(url.as_str().ends_with("/branches/feature%2Ffoo"));
+    }
+
+    fn fake_server() -> testing::FakeServer {
+        let server = testing::FakeServer::start().unwrap();
+        let totals = |lines: usize, hits: usize| totals::Totals {
+            lines,
+            hits,
+            misses: lines - hits,
+            ..Default::default()
+        };
+        let demo = author::Author::new(service::Service::Github, "codecov", "codecov-demo");
+        server.add_commit(
+            &demo,
+            testing::FakeCommit::new(
+                "5a4b2987ca3a8a7b54efac914fd72455ebff50ba",
+                "main",
+                "2023-08-01T15:00:00Z",
+            )
+            .with_file("src/app.py", totals(10, 8)),
+        );
+        server.add_commit(
+            &demo,
+            testing::FakeCommit::new(
+                "1eb341765e7c3daa88ae5d2a751538a620c6dbce",
+                "main",
+                "2023-08-01T15:41:47Z",
+            )
+            .with_parent("5a4b2987ca3a8a7b54efac914fd72455ebff50ba")
+            .with_file("src/app.py", totals(10, 9)),
+        );
+        for i in 0..150 {
+            let author = author::Author::new(
+                service::Service::Github,
+                "codecov",
+                &format!("repo-{:03}", i),
+            );
+            server.add_repo(&author);
+        }
+        server.add_repo(&author::Author::new(
+            service::Service::Github,
+            "kitsuyui",
+            "rust-codecov",
+        ));
+        server
+    }
+
     #[test]
     fn test_get_all_repos() {
-        let client = Client::new_from_env().unwrap();
-        let owner = owner::Owner::new("github", "codecov");
+        let server = fake_server();
+        let client = server.client();
+        let owner = owner::Owner::new(service::Service::Github, "codecov");
         let repos = client.get_all_repos(&owner).unwrap();
-        assert!(!repos.is_empty());
+        assert_eq!(repos.len(), 151);
+        assert_eq!(server.requests().len(), 2);
     }
 
     #[test]
     fn test_get_commits() {
-        let client = Client::new_from_env().unwrap();
-        let author = author::Author::new("github", "codecov", "codecov-demo");
+        let server = fake_server();
+        let client = server.client();
+        let author = author::Author::new(service::Service::Github, "codecov", "codecov-demo");
         let commits = client.get_commits(&author).unwrap();
         assert!(!commits.results.is_empty());
+        let commit = client
+            .get_commit(&author, "1eb341765e7c3daa88ae5d2a751538a620c6dbce")
+            .unwrap();
+        assert_eq!(commit.report.unwrap().files.len(), 1);
     }
 
     #[test]
     fn test_get_branches() {
-        let client = Client::new_from_env().unwrap();
-        let author = author::Author::new("github", "codecov", "codecov-demo");
+        let server = fake_server();
+        let client = server.client();
+        let author = author::Author::new(service::Service::Github, "codecov", "codecov-demo");
         let branches = client.get_branches(&author).unwrap();
         assert!(!branches.results.is_empty());
     }
 
     #[test]
     fn test_get_branch_detail() {
-        let client = Client::new_from_env().unwrap();
-        let author = author::Author::new("github", "codecov", "codecov-demo");
+        let server = fake_server();
+        let client = server.client();
+        let author = author::Author::new(service::Service::Github, "codecov", "codecov-demo");
         let branch_name = "main";
         let branch_detail = client.get_branch_detail(&author, branch_name).unwrap();
         match branch_detail {
             branch_detail::BranchDetailAPIResponse::Success(detail) => {
                 assert_eq!(detail.name, branch_name);
-                assert!(detail.latest_coverage() > 0.0)
+                assert!(detail.latest_coverage() > 0.0);
+                assert_eq!(detail.latest_coverage(), 90.0);
             }
             _ => panic!("should be success"),
         }
@@ -223,12 +629,110 @@ mod tests {
 
     #[test]
     fn test_get_branch_detail_not_found() {
-        let client = Client::new_from_env().unwrap();
-        let author = author::Author::new("github", "kitsuyui", "rust-codecov");
+        let server = fake_server();
+        let client = server.client();
+        let author = author::Author::new(service::Service::Github, "kitsuyui", "rust-codecov");
         let branch_name = "aaaaaaaaaa";
         let branch_detail = client.get_branch_detail(&author, branch_name).unwrap();
         if let branch_detail::BranchDetailAPIResponse::Success(_) = branch_detail {
             panic!("should be not found");
         }
     }
+
+    #[cfg(feature = "tracing")]
+    type Span = (String, Vec<(String, String)>);
+
+    /**
+     * CapturedSpans is a minimal subscriber that keeps the name and fields of every span.
+     */
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct CapturedSpans {
+        spans: std::sync::Arc<std::sync::Mutex<Vec<Span>>>,
+    }
+
+    #[cfg(feature = "tracing")]
+    struct FieldVisitor<'a>(&'a mut Vec<(String, String)>);
+
+    #[cfg(feature = "tracing")]
+    impl tracing::field::Visit for FieldVisitor<'_> {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.push((field.name().to_string(), value.to_string()));
+        }
+
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for CapturedSpans {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut spans = self.spans.lock().unwrap();
+            let mut fields = vec![];
+            span.record(&mut FieldVisitor(&mut fields));
+            spans.push((span.metadata().name().to_string(), fields));
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+            let mut spans = self.spans.lock().unwrap();
+            let index = span.into_u64() as usize - 1;
+            values.record(&mut FieldVisitor(&mut spans[index].1));
+        }
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, _: &tracing::Event<'_>) {}
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_spans() {
+        let server = fake_server();
+        let secret = "secret-token-0123456789";
+        server.set_token(secret);
+        let client = Client::new(secret.to_string()).with_api_endpoint(&server.api_endpoint());
+        let author: Author = "github/codecov/codecov-demo".parse().unwrap();
+        let captured = CapturedSpans::default();
+        tracing::subscriber::with_default(captured.clone(), || {
+            client.get_branch_detail(&author, "main").unwrap();
+            assert!(client.get_commit(&author, "missing").is_err());
+        });
+
+        let spans = captured.spans.lock().unwrap();
+        let field = |name: &str, key: &str| -> Vec<String> {
+            spans
+                .iter()
+                .filter(|(span, _)| span == name)
+                .flat_map(|(_, fields)| fields.iter().filter(|(k, _)| k == key))
+                .map(|(_, value)| value.clone())
+                .collect()
+        };
+        assert_eq!(
+            field("get_branch_detail", "repo"),
+            vec!["github/codecov/codecov-demo"]
+        );
+        assert_eq!(field("get_branch_detail", "branch"), vec!["main"]);
+        assert_eq!(
+            field("codecov.request", "endpoint"),
+            vec!["branch_detail", "commit"]
+        );
+        assert_eq!(field("codecov.request", "status"), vec!["200", "404"]);
+        assert_eq!(field("codecov.request", "latency_ms").len(), 2);
+        for (_, fields) in spans.iter() {
+            for (key, value) in fields {
+                assert!(!value.contains(secret), "token in field {}", key);
+            }
+        }
+    }
 }
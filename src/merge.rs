@@ -0,0 +1,363 @@
+/**
+ * Merging of several coverage reports the way Codecov merges uploads (sessions) of a commit.
+ */
+use crate::coverage::{CoverageReport, FileCoverage, LineCoverage, LineSession, Session};
+
+/**
+ * MergeStrategy decides how the hit counts of the same line are combined.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    #[default]
+    Sum,
+    Max,
+}
+
+/**
+ * Merges the coverage of a single line.
+ * A hit in any report makes the line a hit, and a partial beats a miss.
+ * Two partials keep the best branch count since the branch identities are unknown,
+ * and a partial with unknown branch counts gives way to any known partial that is covered.
+ * An executed line without taken branches stays partial unless the other report took some of them.
+ * Branch counts are kept even when the best count covers every branch.
+ */
+pub fn merge_line(a: &LineCoverage, b: &LineCoverage, strategy: MergeStrategy) -> LineCoverage {
+    match (a, b) {
+        (LineCoverage::Hit(x), LineCoverage::Hit(y)) => match strategy {
+            MergeStrategy::Sum => LineCoverage::Hit(x.saturating_add(*y)),
+            MergeStrategy::Max => LineCoverage::Hit(*x.max(y)),
+        },
+        (LineCoverage::Hit(hits), _) | (_, LineCoverage::Hit(hits)) => LineCoverage::Hit(*hits),
+        (
+            LineCoverage::Partial {
+                covered: c1,
+                total: t1,
+            },
+            LineCoverage::Partial {
+                covered: c2,
+                total: t2,
+            },
+        ) => {
+            let total = *t1.max(t2);
+            let covered = *c1.max(c2).min(&total);
+            LineCoverage::Partial { covered, total }
+        }
+        (
+            LineCoverage::PartialUntaken { total: t1 },
+            LineCoverage::PartialUntaken { total: t2 },
+        ) => LineCoverage::PartialUntaken { total: *t1.max(t2) },
+        (
+            LineCoverage::PartialUntaken { total: t1 },
+            LineCoverage::Partial { covered, total: t2 },
+        )
+        | (
+            LineCoverage::Partial { covered, total: t2 },
+            LineCoverage::PartialUntaken { total: t1 },
+        ) => {
+            let total = *t1.max(t2);
+            match *covered.min(&total) {
+                0 => LineCoverage::PartialUntaken { total },
+                covered => LineCoverage::Partial { covered, total },
+            }
+        }
+        (LineCoverage::PartialUnknown, known) | (known, LineCoverage::PartialUnknown)
+            if known.is_covered() =>
+        {
+            known.clone()
+        }
+        (LineCoverage::PartialUnknown, _) | (_, LineCoverage::PartialUnknown) => {
+            LineCoverage::PartialUnknown
+        }
+        (partial @ LineCoverage::Partial { .. }, LineCoverage::Miss)
+        | (LineCoverage::Miss, partial @ LineCoverage::Partial { .. })
+        | (partial @ LineCoverage::PartialUntaken { .. }, LineCoverage::Miss)
+        | (LineCoverage::Miss, partial @ LineCoverage::PartialUntaken { .. }) => partial.clone(),
+        (LineCoverage::Miss, LineCoverage::Miss) => LineCoverage::Miss,
+    }
+}
+
+/**
+ * Merges other into existing.
+ * Session indexes of other are shifted by session_offset.
+ */
+pub fn merge_file(
+    existing: &mut FileCoverage,
+    other: &FileCoverage,
+    strategy: MergeStrategy,
+    session_offset: usize,
+) {
+    for (line, coverage) in &other.lines {
+        let merged = match existing.lines.get(line) {
+            Some(current) => merge_line(current, coverage, strategy),
+            None => coverage.clone(),
+        };
+        existing.lines.insert(*line, merged);
+    }
+    for (line, sessions) in &other.sessions {
+        existing
+            .sessions
+            .entry(*line)
+            .or_default()
+            .extend(sessions.iter().map(|s| LineSession {
+                session: s.session + session_offset,
+                coverage: s.coverage.clone(),
+            }));
+    }
+    existing.branches = existing.branches.max(other.branches);
+    existing.methods = existing.methods.max(other.methods);
+}
+
+impl CoverageReport {
+    /**
+     * Tags every line of this report as coming from a single session.
+     * Any session information already present is replaced.
+     */
+    pub fn into_session(mut self, session: Session) -> CoverageReport {
+        for file in self.files.values_mut() {
+            file.sessions = file
+                .lines
+                .iter()
+                .map(|(line, coverage)| {
+                    (
+                        *line,
+                        vec![LineSession {
+                            session: 0,
+                            coverage: coverage.clone(),
+                        }],
+                    )
+                })
+                .collect();
+        }
+        self.sessions = vec![session];
+        self
+    }
+
+    /**
+     * Merges other into this report.
+     * Files are unioned, lines are combined with merge_line and the sessions of other are appended.
+     */
+    pub fn merge(&mut self, other: &CoverageReport, strategy: MergeStrategy) {
+        let offset = self.sessions.len();
+        for (name, file) in &other.files {
+            let existing = self.files.entry(name.clone()).or_default();
+            merge_file(existing, file, strategy, offset);
+        }
+        self.sessions.extend(other.sessions.iter().cloned());
+    }
+
+    /**
+     * Returns a report rebuilt only from the sessions carrying any of the given flags.
+     * Lines without session information are dropped.
+     */
+    pub fn filter_flags(&self, flags: &[&str]) -> CoverageReport {
+        let mut mapping = vec![None; self.sessions.len()];
+        let mut sessions = Vec::new();
+        for (index, session) in self.sessions.iter().enumerate() {
+            if session
+                .flags
+                .iter()
+                .any(|flag| flags.contains(&flag.as_str()))
+            {
+                mapping[index] = Some(sessions.len());
+                sessions.push(session.clone());
+            }
+        }
+
+        let mut report = CoverageReport {
+            sessions,
+            ..CoverageReport::default()
+        };
+        for (name, file) in &self.files {
+            let mut filtered = FileCoverage {
+                branches: file.branches,
+                methods: file.methods,
+                ..FileCoverage::default()
+            };
+            for (line, line_sessions) in &file.sessions {
+                let kept: Vec<LineSession> = line_sessions
+                    .iter()
+                    .filter_map(|s| {
+                        let session = (*mapping.get(s.session)?)?;
+                        Some(LineSession {
+                            session,
+                            coverage: s.coverage.clone(),
+                        })
+                    })
+                    .collect();
+                let coverage = kept
+                    .iter()
+                    .map(|s| s.coverage.clone())
+                    .reduce(|a, b| merge_line(&a, &b, MergeStrategy::Sum));
+                if let Some(coverage) = coverage {
+                    filtered.lines.insert(*line, coverage);
+                    filtered.sessions.insert(*line, kept);
+                }
+            }
+            if !filtered.lines.is_empty() {
+                report.files.insert(name.clone(), filtered);
+            }
+        }
+        report
+    }
+}
+
+/**
+ * Merges several reports into one.
+ */
+pub fn merge<I>(reports: I, strategy: MergeStrategy) -> CoverageReport
+where
+    I: IntoIterator<Item = CoverageReport>,
+{
+    let mut merged = CoverageReport::default();
+    for report in reports {
+        merged.merge(&report, strategy);
+    }
+    merged
+}
+
+/**
+ * Merges several uploads, tagging each report with its session first.
+ */
+pub fn merge_sessions<I>(uploads: I, strategy: MergeStrategy) -> CoverageReport
+where
+    I: IntoIterator<Item = (Session, CoverageReport)>,
+{
+    merge(
+        uploads
+            .into_iter()
+            .map(|(session, report)| report.into_session(session)),
+        strategy,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(name: &str, lines: &[(u32, LineCoverage)]) -> CoverageReport {
+        let mut file = FileCoverage::default();
+        for (line, coverage) in lines {
+            file.lines.insert(*line, coverage.clone());
+        }
+        let mut report = CoverageReport::default();
+        report.files.insert(name.to_string(), file);
+        report
+    }
+
+    #[test]
+    fn test_merge_line() {
+        let partial = LineCoverage::Partial {
+            covered: 1,
+            total: 2,
+        };
+        let sum = MergeStrategy::Sum;
+        let max = MergeStrategy::Max;
+        assert_eq!(
+            merge_line(&LineCoverage::Hit(2), &LineCoverage::Hit(3), sum),
+            LineCoverage::Hit(5)
+        );
+        assert_eq!(
+            merge_line(&LineCoverage::Hit(2), &LineCoverage::Hit(3), max),
+            LineCoverage::Hit(3)
+        );
+        assert_eq!(
+            merge_line(&partial, &LineCoverage::Hit(3), sum),
+            LineCoverage::Hit(3)
+        );
+        assert_eq!(merge_line(&LineCoverage::Miss, &partial, sum), partial);
+        assert_eq!(
+            merge_line(&LineCoverage::Miss, &LineCoverage::Miss, sum),
+            LineCoverage::Miss
+        );
+        assert_eq!(
+            merge_line(
+                &partial,
+                &LineCoverage::Partial {
+                    covered: 2,
+                    total: 2
+                },
+                sum
+            ),
+            LineCoverage::Partial {
+                covered: 2,
+                total: 2
+            }
+        );
+        let untaken = LineCoverage::PartialUntaken { total: 2 };
+        assert_eq!(merge_line(&untaken, &partial, sum), partial);
+        assert_eq!(merge_line(&LineCoverage::Miss, &untaken, sum), untaken);
+        assert_eq!(
+            merge_line(
+                &LineCoverage::Partial {
+                    covered: 0,
+                    total: 2
+                },
+                &untaken,
+                sum
+            ),
+            untaken
+        );
+    }
+
+    #[test]
+    fn test_merge_sessions() {
+        let unit = report(
+            "src/lib.rs",
+            &[
+                (1, LineCoverage::Hit(1)),
+                (
+                    2,
+                    LineCoverage::Partial {
+                        covered: 1,
+                        total: 2,
+                    },
+                ),
+                (3, LineCoverage::Miss),
+            ],
+        );
+        let mut integration = report(
+            "src/lib.rs",
+            &[
+                (1, LineCoverage::Hit(2)),
+                (2, LineCoverage::Hit(1)),
+                (3, LineCoverage::Miss),
+            ],
+        );
+        integration.files.insert(
+            "src/main.rs".to_string(),
+            report("src/main.rs", &[(1, LineCoverage::Hit(1))]).files["src/main.rs"].clone(),
+        );
+
+        let merged = merge_sessions(
+            vec![
+                (Session::new("unit", &["unit"]), unit),
+                (Session::new("integration", &["integration"]), integration),
+            ],
+            MergeStrategy::Sum,
+        );
+        let lib = &merged.files["src/lib.rs"];
+        assert_eq!(lib.lines[&1], LineCoverage::Hit(3));
+        assert_eq!(lib.lines[&2], LineCoverage::Hit(1));
+        assert_eq!(lib.lines[&3], LineCoverage::Miss);
+        assert_eq!(lib.sessions[&1].len(), 2);
+        assert_eq!(lib.sessions[&1][1].session, 1);
+
+        let totals = merged.totals();
+        assert_eq!(totals.files, 2);
+        assert_eq!(totals.hits, 3);
+        assert_eq!(totals.misses, 1);
+        assert_eq!(totals.sessions, 2);
+
+        let unit_only = merged.filter_flags(&["unit"]);
+        assert_eq!(unit_only.sessions.len(), 1);
+        assert_eq!(unit_only.files.len(), 1);
+        assert_eq!(
+            unit_only.files["src/lib.rs"].lines[&2],
+            LineCoverage::Partial {
+                covered: 1,
+                total: 2
+            }
+        );
+        assert_eq!(unit_only.totals().sessions, 1);
+    }
+}
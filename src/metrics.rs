@@ -0,0 +1,155 @@
+/**
+ * Request metrics for Client::with_metrics.
+ * Every HTTP request (not cache hits nor replayed responses) is reported to a Metrics hook,
+ * e.g. CountingMetrics or an adapter to your metrics system.
+ *
+ * ```no_run
+ * use std::sync::Arc;
+ * use codecov::{metrics::CountingMetrics, owner::Owner, Client};
+ *
+ * let metrics = Arc::new(CountingMetrics::new());
+ * let client = Client::new_from_env().unwrap().with_metrics(metrics.clone());
+ * client.get_all_repos(&"github/kitsuyui".parse::<Owner>().unwrap()).unwrap();
+ * for (endpoint, counts) in metrics.counts() {
+ *     println!("{}: {} requests, {} bytes, {} errors", endpoint, counts.requests, counts.bytes, counts.errors);
+ * }
+ * ```
+ */
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::endpoint::Endpoint;
+use crate::Client;
+
+/**
+ * RequestMetrics describes one request.
+ * status is None when no response was received.
+ * A request is an error when no response was received or the status is not 2xx/304.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestMetrics {
+    pub endpoint: Endpoint,
+    pub status: Option<u16>,
+    pub bytes: usize,
+    pub latency: Duration,
+}
+
+impl RequestMetrics {
+    pub fn is_error(&self) -> bool {
+        match self.status {
+            Some(status) => !(200..300).contains(&status) && status != 304,
+            None => true,
+        }
+    }
+}
+
+/**
+ * Metrics is the hook Client calls after every request.
+ */
+pub trait Metrics: Send + Sync {
+    fn record(&self, request: &RequestMetrics);
+}
+
+/**
+ * EndpointCounts are the totals of the requests to an endpoint.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EndpointCounts {
+    pub requests: u64,
+    pub bytes: u64,
+    pub errors: u64,
+}
+
+/**
+ * CountingMetrics counts requests, response bytes and errors per endpoint.
+ */
+#[derive(Debug, Default)]
+pub struct CountingMetrics {
+    counts: Mutex<BTreeMap<Endpoint, EndpointCounts>>,
+}
+
+impl CountingMetrics {
+    pub fn new() -> CountingMetrics {
+        CountingMetrics::default()
+    }
+
+    /**
+     * Returns the counts so far. Endpoints without requests are omitted.
+     */
+    pub fn counts(&self) -> BTreeMap<Endpoint, EndpointCounts> {
+        match self.counts.lock() {
+            Ok(counts) => counts.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+}
+
+impl Metrics for CountingMetrics {
+    fn record(&self, request: &RequestMetrics) {
+        let mut counts = match self.counts.lock() {
+            Ok(counts) => counts,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let counts = counts.entry(request.endpoint).or_default();
+        counts.requests += 1;
+        counts.bytes += request.bytes as u64;
+        if request.is_error() {
+            counts.errors += 1;
+        }
+    }
+}
+
+impl Client {
+    /**
+     * Returns the client that reports every request to metrics.
+     */
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Client {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::author::Author;
+    use crate::testing::{FakeCommit, FakeServer};
+
+    #[test]
+    fn test_is_error() {
+        let request = |status| RequestMetrics {
+            endpoint: Endpoint::Repos,
+            status,
+            bytes: 0,
+            latency: Duration::ZERO,
+        };
+        assert!(!request(Some(200)).is_error());
+        assert!(!request(Some(304)).is_error());
+        assert!(request(Some(404)).is_error());
+        assert!(request(None).is_error());
+    }
+
+    #[test]
+    fn test_counting_metrics() {
+        let server = FakeServer::start().unwrap();
+        let author: Author = "github/kitsuyui/rust-codecov".parse().unwrap();
+        server.add_commit(
+            &author,
+            FakeCommit::new(&format!("{:040x}", 1), "main", "2023-08-01T15:41:47Z"),
+        );
+        let metrics = Arc::new(CountingMetrics::new());
+        let client = server.client().with_metrics(metrics.clone());
+        client.get_branches(&author).unwrap();
+        client.get_branches(&author).unwrap();
+        assert!(client.get_commit(&author, "missing").is_err());
+
+        let counts = metrics.counts();
+        assert_eq!(counts.len(), 2);
+        let branches = counts[&Endpoint::Branches];
+        assert_eq!(branches.requests, 2);
+        assert_eq!(branches.errors, 0);
+        assert!(branches.bytes > 0);
+        assert_eq!(counts[&Endpoint::Commit].errors, 1);
+    }
+}
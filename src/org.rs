@@ -0,0 +1,305 @@
+/**
+ * Org-wide crawling: all repos of an owner and the detail of their default branch,
+ * fetched concurrently into an OrgSnapshot.
+ */
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::branch_detail::{BranchDetailAPIResponse, BranchDetailAPISuccessResponse};
+use crate::errors::Error;
+use crate::owner::Owner;
+use crate::repos::{Repo, ReposAPIResponse};
+use crate::url;
+use crate::Client;
+
+/**
+ * CrawlOptions controls Client::get_org_snapshot_with.
+ * concurrency is the maximum number of requests in flight; 0 is treated as 1.
+ */
+#[derive(Debug, Clone)]
+pub struct CrawlOptions {
+    pub concurrency: usize,
+    pub page_size: usize,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CrawlOptions {
+    pub fn new() -> CrawlOptions {
+        CrawlOptions {
+            concurrency: 8,
+            page_size: 100,
+        }
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> CrawlOptions {
+        self.concurrency = concurrency;
+        self
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> CrawlOptions {
+        self.page_size = page_size;
+        self
+    }
+}
+
+/**
+ * OrgSnapshot is every repo of an owner with the detail of its default branch.
+ * Failures are reported per repo, so one broken repo does not hide the others.
+ */
+#[derive(Debug)]
+pub struct OrgSnapshot {
+    pub owner: Owner,
+    pub repos: Vec<RepoSnapshot>,
+}
+
+/**
+ * RepoSnapshot is a repo and the detail of its default branch,
+ * or the error that prevented fetching it: Error::NotFoundError for repos without a default branch
+ * or whose default branch is not found, Error::HttpError for error responses like 5xx.
+ */
+#[derive(Debug)]
+pub struct RepoSnapshot {
+    pub repo: Repo,
+    pub branch_detail: Result<Box<BranchDetailAPISuccessResponse>, Error>,
+}
+
+impl OrgSnapshot {
+    /**
+     * Returns the repos whose default branch detail was fetched.
+     */
+    pub fn successes(&self) -> Vec<(&Repo, &BranchDetailAPISuccessResponse)> {
+        self.repos
+            .iter()
+            .filter_map(|snapshot| match &snapshot.branch_detail {
+                Ok(detail) => Some((&snapshot.repo, detail.as_ref())),
+                Err(_) => None,
+            })
+            .collect()
+    }
+
+    /**
+     * Returns the repos whose default branch detail could not be fetched, with the reason.
+     */
+    pub fn failures(&self) -> Vec<(&Repo, &Error)> {
+        self.repos
+            .iter()
+            .filter_map(|snapshot| match &snapshot.branch_detail {
+                Ok(_) => None,
+                Err(e) => Some((&snapshot.repo, e)),
+            })
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.repos
+            .iter()
+            .all(|snapshot| snapshot.branch_detail.is_ok())
+    }
+}
+
+impl Client {
+    /**
+     * get_org_snapshot fetches all repos of an owner and the detail of their default branch
+     * with CrawlOptions::default().
+     */
+    pub fn get_org_snapshot(&self, owner: &Owner) -> Result<OrgSnapshot, Error> {
+        self.get_org_snapshot_with(owner, &CrawlOptions::default())
+    }
+
+    /**
+     * get_org_snapshot with custom options.
+     * The first page of repos tells total_pages, the other pages are then fetched concurrently,
+     * followed by the default branch of every repo. Failing to list repos fails the whole snapshot.
+     */
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(owner = %owner))
+    )]
+    pub fn get_org_snapshot_with(
+        &self,
+        owner: &Owner,
+        options: &CrawlOptions,
+    ) -> Result<OrgSnapshot, Error> {
+        let repos_url = url::join_segments(&self.owner_endpoint(owner)?, &["repos"])?;
+        let page_url = |page: usize| {
+            let mut url = repos_url.clone();
+            url.query_pairs_mut()
+                .append_pair("page", &page.to_string())
+                .append_pair("page_size", &options.page_size.to_string());
+            url
+        };
+        let first = self.get_repos_page(&page_url(1))?;
+        let pages: Vec<usize> = (2..=first.total_pages).collect();
+        let mut repos = first.results;
+        for page in parallel_map(&pages, options.concurrency, |page| {
+            self.get_repos_page(&page_url(*page))
+        }) {
+            let ReposAPIResponse { mut results, .. } = page?;
+            repos.append(&mut results);
+        }
+
+        let details = parallel_map(&repos, options.concurrency, |repo| {
+            self.get_default_branch_detail(owner, repo)
+        });
+        Ok(OrgSnapshot {
+            owner: owner.clone(),
+            repos: repos
+                .into_iter()
+                .zip(details)
+                .map(|(repo, branch_detail)| RepoSnapshot {
+                    repo,
+                    branch_detail,
+                })
+                .collect(),
+        })
+    }
+
+    fn get_default_branch_detail(
+        &self,
+        owner: &Owner,
+        repo: &Repo,
+    ) -> Result<Box<BranchDetailAPISuccessResponse>, Error> {
+        let branch = match &repo.branch {
+            Some(branch) => branch,
+            None => {
+                return Err(Error::NotFoundError(format!(
+                    "{} has no default branch",
+                    repo.name
+                )))
+            }
+        };
+        match self.get_branch_detail(&owner.new_author(&repo.name), branch)? {
+            BranchDetailAPIResponse::Success(detail) => Ok(detail),
+            BranchDetailAPIResponse::NotFound(not_found) => {
+                Err(Error::NotFoundError(not_found.detail))
+            }
+        }
+    }
+}
+
+/**
+ * Applies f to every item on up to concurrency threads and returns the results in the order of items.
+ * With the `tracing` feature, the threads run in the span of the caller.
+ */
+fn parallel_map<T, R, F>(items: &[T], concurrency: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    #[cfg(feature = "tracing")]
+    let span = tracing::Span::current();
+    let workers = concurrency.clamp(1, items.len().max(1));
+    let mut results: Vec<(usize, R)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    #[cfg(feature = "tracing")]
+                    let _entered = span.enter();
+                    let mut results = vec![];
+                    loop {
+                        let index = next.fetch_add(1, Ordering::SeqCst);
+                        match items.get(index) {
+                            Some(item) => results.push((index, f(item))),
+                            None => return results,
+                        }
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| match handle.join() {
+                Ok(results) => results,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect()
+    });
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::author::Author;
+    use crate::testing::{FakeCommit, FakeServer};
+    use serde_json::json;
+
+    #[test]
+    fn test_parallel_map() {
+        let items: Vec<usize> = (0..50).collect();
+        assert_eq!(
+            parallel_map(&items, 4, |i| i * 2),
+            (0..100).step_by(2).collect::<Vec<_>>()
+        );
+        assert_eq!(parallel_map(&items[..0], 4, |i| *i), Vec::<usize>::new());
+        assert_eq!(parallel_map(&items[..3], 0, |i| *i), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_get_org_snapshot() {
+        let server = FakeServer::start().unwrap();
+        let owner: Owner = "github/kitsuyui".parse().unwrap();
+        for i in 0..25 {
+            server.add_commit(
+                &owner.new_author(&format!("repo-{:02}", i)),
+                FakeCommit::new(&format!("{:040x}", i), "main", "2023-08-01T15:41:47Z"),
+            );
+        }
+        let empty: Author = "github/kitsuyui/empty".parse().unwrap();
+        server.add_repo(&empty);
+        server.respond_with(
+            "/api/v2/github/kitsuyui/repos/repo-03/branches/main",
+            500,
+            json!({"detail": "A server error occurred."}),
+        );
+        server.respond_with(
+            "/api/v2/github/kitsuyui/repos/repo-05/branches/main",
+            404,
+            json!({"detail": "Not found."}),
+        );
+        let client = server.client();
+        let options = CrawlOptions::new().with_page_size(10).with_concurrency(3);
+        let snapshot = client.get_org_snapshot_with(&owner, &options).unwrap();
+        assert_eq!(snapshot.repos.len(), 26);
+        assert_eq!(snapshot.repos[0].repo.name, "repo-00");
+        assert_eq!(snapshot.repos[25].repo.name, "empty");
+        assert_eq!(snapshot.successes().len(), 23);
+        let failures = snapshot.failures();
+        let names: Vec<&str> = failures
+            .iter()
+            .map(|(repo, _)| repo.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["repo-03", "repo-05", "empty"]);
+        assert!(matches!(
+            failures[0].1,
+            Error::HttpError { status: 500, detail } if detail == "A server error occurred."
+        ));
+        assert!(matches!(failures[1].1, Error::NotFoundError(_)));
+        assert!(matches!(failures[2].1, Error::NotFoundError(_)));
+        assert!(!snapshot.is_complete());
+        // 3 pages of repos and a branch detail for every repo with a default branch.
+        assert_eq!(server.requests().len(), 3 + 25);
+    }
+
+    #[test]
+    fn test_get_org_snapshot_fails_on_repo_list() {
+        let server = FakeServer::start().unwrap();
+        server.respond_with(
+            "/api/v2/github/kitsuyui/repos",
+            500,
+            json!({"detail": "A server error occurred."}),
+        );
+        let owner: Owner = "github/kitsuyui".parse().unwrap();
+        assert!(matches!(
+            server.client().get_org_snapshot(&owner),
+            Err(Error::HttpError { status: 500, .. })
+        ));
+    }
+}
@@ -1,16 +1,22 @@
+use std::fmt;
+use std::str::FromStr;
+
 use crate::author::Author;
+use crate::errors::Error;
+use crate::service::Service;
 /**
  * Owner is a struct that represents an owner of repos.
  */
+#[derive(Debug, Clone)]
 pub struct Owner {
-    pub service: String,
+    pub service: Service,
     pub username: String,
 }
 
 impl Owner {
-    pub fn new(service: &str, username: &str) -> Owner {
+    pub fn new(service: Service, username: &str) -> Owner {
         Owner {
-            service: service.to_string(),
+            service,
             username: username.to_string(),
         }
     }
@@ -22,3 +28,40 @@ impl Owner {
         Author::from_owner(self, name)
     }
 }
+
+impl FromStr for Owner {
+    type Err = Error;
+
+    /**
+     * Parses an owner slug like "github/kitsuyui" or "gh/kitsuyui".
+     */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim_matches('/').split_once('/') {
+            Some((service, username)) if !username.is_empty() && !username.contains('/') => {
+                Ok(Owner::new(service.parse()?, username))
+            }
+            _ => Err(Error::ParseError(format!("expected service/owner: {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for Owner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.service, self.username)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        let owner: Owner = "gh/kitsuyui".parse().unwrap();
+        assert_eq!(owner.service, Service::Github);
+        assert_eq!(owner.username, "kitsuyui");
+        assert_eq!(owner.to_string(), "github/kitsuyui");
+        assert!("github".parse::<Owner>().is_err());
+        assert!("github/kitsuyui/rust-codecov".parse::<Owner>().is_err());
+    }
+}
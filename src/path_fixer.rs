@@ -0,0 +1,362 @@
+/**
+ * Path normalization for coverage reports, following codecov.yml `fixes` and `ignore`.
+ * https://docs.codecov.com/docs/fixing-paths
+ */
+use std::collections::BTreeSet;
+
+use crate::coverage::CoverageReport;
+use crate::errors::Error;
+use crate::merge::{merge_file, MergeStrategy};
+
+/**
+ * Fix is a single `before::after` rewrite rule.
+ * An empty before adds a prefix and an empty after strips one.
+ * before may contain glob wildcards (`*`, `**` and `?`).
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    pub before: String,
+    pub after: String,
+}
+
+impl Fix {
+    /**
+     * Parses a rule in codecov.yml syntax like "before/::after/".
+     */
+    pub fn parse(rule: &str) -> Result<Fix, Error> {
+        match rule.split_once("::") {
+            Some((before, after)) => Ok(Fix {
+                before: before.to_string(),
+                after: after.to_string(),
+            }),
+            None => Err(Error::ConfigError(format!(
+                "invalid fix {:?}: expected \"before::after\"",
+                rule
+            ))),
+        }
+    }
+
+    /**
+     * Applies this rule to a path, returning None if the rule does not match.
+     * The longest prefix matching before is replaced.
+     */
+    pub fn apply(&self, path: &str) -> Option<String> {
+        if self.before.is_empty() {
+            return Some(format!("{}{}", self.after, path));
+        }
+        if !has_wildcard(&self.before) {
+            let rest = path.strip_prefix(&self.before)?;
+            return Some(format!("{}{}", self.after, rest));
+        }
+        let end = path
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(path.len()))
+            .rev()
+            .find(|i| glob_match(&self.before, &path[..*i]))?;
+        Some(format!("{}{}", self.after, &path[end..]))
+    }
+}
+
+/**
+ * PathFixer is a struct that rewrites, ignores and resolves report paths.
+ *
+ * ```
+ * use codecov::path_fixer::PathFixer;
+ *
+ * let fixer = PathFixer::new()
+ *     .with_fixes(&["/app/::"])
+ *     .unwrap()
+ *     .with_ignore(&["tests"]);
+ * assert_eq!(fixer.fix("/app/src/lib.rs"), Some("src/lib.rs".to_string()));
+ * assert_eq!(fixer.fix("/app/tests/it.rs"), None);
+ * ```
+ */
+#[derive(Debug, Clone, Default)]
+pub struct PathFixer {
+    fixes: Vec<Fix>,
+    ignore: Vec<String>,
+    repo_files: Option<BTreeSet<String>>,
+}
+
+impl PathFixer {
+    pub fn new() -> PathFixer {
+        PathFixer::default()
+    }
+
+    /**
+     * Adds `fixes` rules, applied in order.
+     */
+    pub fn with_fixes(mut self, fixes: &[&str]) -> Result<PathFixer, Error> {
+        for fix in fixes {
+            self.fixes.push(Fix::parse(fix)?);
+        }
+        Ok(self)
+    }
+
+    /**
+     * Adds `ignore` globs. A path is ignored if it or one of its parent directories matches.
+     */
+    pub fn with_ignore(mut self, patterns: &[&str]) -> PathFixer {
+        self.ignore
+            .extend(patterns.iter().map(|pattern| pattern.to_string()));
+        self
+    }
+
+    /**
+     * Sets the list of files in the repository.
+     * Paths that are not in the list are resolved to the repository file sharing the longest suffix,
+     * and dropped if there is no such file or the match is ambiguous.
+     */
+    pub fn with_repo_files(mut self, files: &[&str]) -> PathFixer {
+        self.repo_files = Some(files.iter().map(|file| normalize(file)).collect());
+        self
+    }
+
+    /**
+     * Returns the fixed path, or None if the path is ignored or cannot be resolved.
+     */
+    pub fn fix(&self, path: &str) -> Option<String> {
+        let mut path = normalize(path);
+        for fix in &self.fixes {
+            if let Some(fixed) = fix.apply(&path) {
+                path = normalize(&fixed);
+            }
+        }
+        if self.is_ignored(&path) {
+            return None;
+        }
+        match &self.repo_files {
+            Some(files) => resolve(files, &path),
+            None => Some(path),
+        }
+    }
+
+    /**
+     * Returns true if the path matches one of the ignore globs.
+     */
+    pub fn is_ignored(&self, path: &str) -> bool {
+        self.ignore
+            .iter()
+            .any(|pattern| path_matches(pattern, path))
+    }
+
+    /**
+     * Returns a report with every path fixed.
+     * Files that are ignored or unresolved are dropped, and files that end up with the same path are merged.
+     */
+    pub fn apply(&self, report: &CoverageReport) -> CoverageReport {
+        let mut fixed = CoverageReport {
+            sessions: report.sessions.clone(),
+            ..CoverageReport::default()
+        };
+        for (name, file) in &report.files {
+            if let Some(name) = self.fix(name) {
+                let existing = fixed.files.entry(name).or_default();
+                merge_file(existing, file, MergeStrategy::Sum, 0);
+            }
+        }
+        fixed
+    }
+}
+
+/**
+ * Normalizes separators and removes `./` and duplicate slashes.
+ */
+fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let absolute = path.starts_with('/');
+    let parts: Vec<&str> = path
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect();
+    let joined = parts.join("/");
+    if absolute {
+        format!("/{}", joined)
+    } else {
+        joined
+    }
+}
+
+/**
+ * Resolves a path against the repository files by the longest common suffix of path components.
+ */
+fn resolve(files: &BTreeSet<String>, path: &str) -> Option<String> {
+    if files.contains(path) {
+        return Some(path.to_string());
+    }
+    let components: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    let mut best: Option<&String> = None;
+    let mut best_len = 0;
+    let mut ambiguous = false;
+    for file in files {
+        let len = file
+            .split('/')
+            .rev()
+            .zip(components.iter().rev())
+            .take_while(|(a, b)| a == *b)
+            .count();
+        if len == 0 {
+            continue;
+        }
+        if len > best_len {
+            best = Some(file);
+            best_len = len;
+            ambiguous = false;
+        } else if len == best_len {
+            ambiguous = true;
+        }
+    }
+    if ambiguous {
+        return None;
+    }
+    best.cloned()
+}
+
+/**
+ * Returns true if the path or one of its parent directories matches the glob.
+ */
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+    glob_match(pattern, path)
+        || path
+            .match_indices('/')
+            .any(|(i, _)| glob_match(pattern, &path[..i]))
+}
+
+fn has_wildcard(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/**
+ * Matches a path against a glob.
+ * `*` and `?` do not cross `/`, while `**` matches any number of directories.
+ */
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    glob_match_chars(&pattern, &path)
+}
+
+fn glob_match_chars(pattern: &[char], path: &[char]) -> bool {
+    match pattern.first() {
+        None => path.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let mut rest = &pattern[2..];
+            if rest.first() == Some(&'/') {
+                // "**/" also matches zero directories.
+                rest = &rest[1..];
+                if glob_match_chars(rest, path) {
+                    return true;
+                }
+                return path
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c == '/')
+                    .any(|(i, _)| glob_match_chars(rest, &path[i + 1..]));
+            }
+            (0..=path.len()).any(|i| glob_match_chars(rest, &path[i..]))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for i in 0..=path.len() {
+                if glob_match_chars(rest, &path[i..]) {
+                    return true;
+                }
+                if path.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => match path.first() {
+            Some(c) if *c != '/' => glob_match_chars(&pattern[1..], &path[1..]),
+            _ => false,
+        },
+        Some(c) => path.first() == Some(c) && glob_match_chars(&pattern[1..], &path[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coverage::{FileCoverage, LineCoverage};
+
+    #[test]
+    fn test_fix() {
+        assert_eq!(
+            Fix::parse("/app/::").unwrap().apply("/app/src/lib.rs"),
+            Some("src/lib.rs".to_string())
+        );
+        assert_eq!(
+            Fix::parse("::crates/core/").unwrap().apply("src/lib.rs"),
+            Some("crates/core/src/lib.rs".to_string())
+        );
+        assert_eq!(
+            Fix::parse("/build/*/src/::src/")
+                .unwrap()
+                .apply("/build/1234/src/lib.rs"),
+            Some("src/lib.rs".to_string())
+        );
+        assert_eq!(Fix::parse("/app/::").unwrap().apply("src/lib.rs"), None);
+        assert!(Fix::parse("/app/").is_err());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("src/*.rs", "src/lib.rs"));
+        assert!(!glob_match("src/*.rs", "src/a/lib.rs"));
+        assert!(glob_match("src/**/*.rs", "src/lib.rs"));
+        assert!(glob_match("src/**/*.rs", "src/a/b/lib.rs"));
+        assert!(glob_match("**/tests", "a/b/tests"));
+        assert!(glob_match("src/?.rs", "src/a.rs"));
+        assert!(!glob_match("src/?.rs", "src/ab.rs"));
+    }
+
+    #[test]
+    fn test_ignore() {
+        let fixer = PathFixer::new().with_ignore(&["tests/", "**/generated", "*.pb.rs"]);
+        assert!(fixer.is_ignored("tests/it.rs"));
+        assert!(fixer.is_ignored("src/generated/a.rs"));
+        assert!(fixer.is_ignored("a.pb.rs"));
+        assert!(!fixer.is_ignored("src/lib.rs"));
+    }
+
+    #[test]
+    fn test_repo_files() {
+        let fixer = PathFixer::new().with_repo_files(&[
+            "src/lib.rs",
+            "a/mod.rs",
+            "b/mod.rs",
+            "./README.md",
+        ]);
+        assert_eq!(fixer.fix("/app/src/lib.rs"), Some("src/lib.rs".to_string()));
+        assert_eq!(fixer.fix("README.md"), Some("README.md".to_string()));
+        assert_eq!(fixer.fix("/app/mod.rs"), None);
+        assert_eq!(fixer.fix("/app/b/mod.rs"), Some("b/mod.rs".to_string()));
+        assert_eq!(fixer.fix("main.rs"), None);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut report = CoverageReport::default();
+        let mut a = FileCoverage::default();
+        a.lines.insert(1, LineCoverage::Hit(1));
+        a.lines.insert(2, LineCoverage::Miss);
+        let mut b = FileCoverage::default();
+        b.lines.insert(2, LineCoverage::Hit(1));
+        report.files.insert("/app/src/lib.rs".to_string(), a);
+        report.files.insert("./src/lib.rs".to_string(), b);
+        report
+            .files
+            .insert("/app/tests/it.rs".to_string(), FileCoverage::default());
+
+        let fixer = PathFixer::new()
+            .with_fixes(&["/app/::"])
+            .unwrap()
+            .with_ignore(&["tests"]);
+        let fixed = fixer.apply(&report);
+        assert_eq!(fixed.files.len(), 1);
+        assert_eq!(fixed.totals().hits, 2);
+    }
+}
@@ -0,0 +1,353 @@
+/**
+ * Record-and-replay of API traffic.
+ * A Client with a recording transport saves every request and response to a fixture file,
+ * and a Client with a replaying transport serves them back without any network.
+ *
+ * The fixture is a JSON file like this (the token is never written):
+ * ```json
+ * {"interactions": [{
+ *   "request": {"method": "GET", "path": "github/kitsuyui/repos?page_size=100",
+ *               "headers": {"Authorization": "bearer [REDACTED]"}},
+ *   "response": {"status": 200, "body": {"count": 1, "results": []}}
+ * }]}
+ * ```
+ *
+ * The path is relative to the API endpoint of the client, so a fixture recorded against
+ * a testing::FakeServer or a self-hosted Codecov replays with any endpoint.
+ */
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::Error;
+use crate::url::Url;
+
+/**
+ * REDACTED replaces secrets in recorded requests.
+ */
+pub const REDACTED: &str = "[REDACTED]";
+
+/**
+ * Fixture is the content of a fixture file.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Fixture {
+    pub interactions: Vec<Interaction>,
+}
+
+/**
+ * Interaction is a request and the response it got.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Fixture {
+    /**
+     * Reads a fixture file.
+     */
+    pub fn load(path: &Path) -> Result<Fixture, Error> {
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) => return Err(Error::IoError(e)),
+        };
+        match serde_json::from_str::<Fixture>(&json) {
+            Ok(fixture) => Ok(fixture),
+            Err(e) => Err(Error::DeserializeError(e)),
+        }
+    }
+
+    /**
+     * Writes the fixture as pretty-printed JSON, creating parent directories if needed.
+     */
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let json = match serde_json::to_string_pretty(self) {
+            Ok(json) => json,
+            Err(e) => return Err(Error::SerializeError(e)),
+        };
+        if let Some(parent) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                return Err(Error::IoError(e));
+            }
+        }
+        match std::fs::write(path, json + "\n") {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Error::IoError(e)),
+        }
+    }
+}
+
+/**
+ * Transport decides how Client sends requests.
+ */
+#[derive(Clone)]
+pub(crate) enum Transport {
+    Http,
+    Record(Arc<Recorder>),
+    Replay(Arc<Replayer>),
+}
+
+/**
+ * Recorder saves interactions to a fixture file after every request,
+ * so the file is complete even if the program stops early.
+ */
+pub(crate) struct Recorder {
+    path: PathBuf,
+    fixture: Mutex<Fixture>,
+}
+
+impl Recorder {
+    pub(crate) fn new(path: &Path) -> Recorder {
+        Recorder {
+            path: path.to_path_buf(),
+            fixture: Mutex::new(Fixture::default()),
+        }
+    }
+
+    pub(crate) fn record(
+        &self,
+        method: &str,
+        path: &str,
+        status: u16,
+        body: &Value,
+    ) -> Result<(), Error> {
+        let mut fixture = match self.fixture.lock() {
+            Ok(fixture) => fixture,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let mut headers = BTreeMap::new();
+        headers.insert("Authorization".to_string(), format!("bearer {}", REDACTED));
+        fixture.interactions.push(Interaction {
+            request: RecordedRequest {
+                method: method.to_string(),
+                path: path.to_string(),
+                headers,
+            },
+            response: RecordedResponse {
+                status,
+                body: body.clone(),
+            },
+        });
+        fixture.save(&self.path)
+    }
+}
+
+/**
+ * Replayer serves the recorded responses.
+ * Requests are matched by method and path. Repeated requests get the recorded responses in order,
+ * and the last one again once they are used up. Unmatched requests are an Error::ReplayError.
+ */
+pub(crate) struct Replayer {
+    fixture: Fixture,
+    used: Mutex<Vec<bool>>,
+}
+
+impl Replayer {
+    pub(crate) fn new(fixture: Fixture) -> Replayer {
+        let used = vec![false; fixture.interactions.len()];
+        Replayer {
+            fixture,
+            used: Mutex::new(used),
+        }
+    }
+
+    /**
+     * Returns the recorded status and body of a request.
+     */
+    pub(crate) fn replay(&self, method: &str, path: &str) -> Result<(u16, Value), Error> {
+        let mut used = match self.used.lock() {
+            Ok(used) => used,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let matches: Vec<usize> = self
+            .fixture
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| i.request.method == method && i.request.path == path)
+            .map(|(index, _)| index)
+            .collect();
+        let index = match matches
+            .iter()
+            .find(|index| !used[**index])
+            .or(matches.last())
+        {
+            Some(index) => *index,
+            None => {
+                return Err(Error::ReplayError(format!(
+                    "no recorded response for {} {}",
+                    method, path
+                )))
+            }
+        };
+        used[index] = true;
+        let response = &self.fixture.interactions[index].response;
+        Ok((response.status, response.body.clone()))
+    }
+}
+
+/**
+ * Returns the path and query of url relative to api_endpoint, e.g. "github/kitsuyui/repos?page=2".
+ * URLs outside of api_endpoint keep their full path.
+ */
+pub(crate) fn relative_path(api_endpoint: &str, url: &Url) -> String {
+    let segments = |url: &Url| -> Vec<String> {
+        match url.path_segments() {
+            Some(segments) => segments
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+                .collect(),
+            None => vec![],
+        }
+    };
+    let path = match Url::parse(api_endpoint) {
+        Ok(base) => {
+            let base = segments(&base);
+            let segments = segments(url);
+            match segments.strip_prefix(base.as_slice()) {
+                Some(rest) => rest.join("/"),
+                None => url.path().to_string(),
+            }
+        }
+        Err(_) => url.path().to_string(),
+    };
+    match url.query() {
+        Some(query) => format!("{}?{}", path, query),
+        None => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::author::Author;
+    use crate::testing::{FakeCommit, FakeServer};
+    use crate::Client;
+
+    fn fixture_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("codecov-replay-{}", std::process::id()))
+            .join(name)
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = fixture_path("record_and_replay.json");
+        let author: Author = "github/kitsuyui/rust-codecov".parse().unwrap();
+        let recorded = {
+            let server = FakeServer::start().unwrap();
+            server.add_commit(
+                &author,
+                FakeCommit::new(
+                    "1eb341765e7c3daa88ae5d2a751538a620c6dbce",
+                    "main",
+                    "2023-08-01T15:41:47Z",
+                ),
+            );
+            let client = server.client().with_recording(&path);
+            let branches = client.get_branches(&author).unwrap();
+            assert!(client.get_branch_detail(&author, "missing").is_ok());
+            server.respond_with(
+                "/api/v2/github/kitsuyui/repos/rust-codecov/commits",
+                500,
+                serde_json::json!({"detail": "A server error occurred."}),
+            );
+            assert!(client.get_commits(&author).is_err());
+            branches
+        };
+
+        let fixture = std::fs::read_to_string(&path).unwrap();
+        assert!(fixture.contains(REDACTED));
+        assert!(!fixture.contains(crate::testing::FAKE_TOKEN));
+
+        // The server is gone, so these can only come from the fixture.
+        let client = Client::new("another-token".to_string())
+            .with_api_endpoint("https://codecov.example.com/api/v2/")
+            .with_replay(&path)
+            .unwrap();
+        let replayed = client.get_branches(&author).unwrap();
+        assert_eq!(replayed.results[0].name, recorded.results[0].name);
+        assert_eq!(client.get_branches(&author).unwrap().count, 1);
+        assert!(matches!(
+            client.get_branch_detail(&author, "missing").unwrap(),
+            crate::branch_detail::BranchDetailAPIResponse::NotFound(_)
+        ));
+        assert!(matches!(
+            client.get_commits(&author),
+            Err(Error::HttpError { status: 500, .. })
+        ));
+        assert!(matches!(
+            client.get_commit_uploads(&author, "1eb341765e7c3daa88ae5d2a751538a620c6dbce"),
+            Err(Error::ReplayError(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_in_order() {
+        let path = "github/kitsuyui/repos";
+        let interaction = |status: u16, n: u64| Interaction {
+            request: RecordedRequest {
+                method: "GET".to_string(),
+                path: path.to_string(),
+                headers: BTreeMap::new(),
+            },
+            response: RecordedResponse {
+                status,
+                body: Value::from(n),
+            },
+        };
+        let replayer = Replayer::new(Fixture {
+            interactions: vec![interaction(200, 1), interaction(503, 2)],
+        });
+        assert_eq!(replayer.replay("GET", path).unwrap(), (200, Value::from(1)));
+        assert_eq!(replayer.replay("GET", path).unwrap(), (503, Value::from(2)));
+        assert_eq!(replayer.replay("GET", path).unwrap(), (503, Value::from(2)));
+        assert!(replayer.replay("POST", path).is_err());
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let url = |s: &str| Url::parse(s).unwrap();
+        assert_eq!(
+            relative_path(
+                "https://codecov.io/api/v2",
+                &url("https://codecov.io/api/v2/github/kitsuyui/repos?page=2")
+            ),
+            "github/kitsuyui/repos?page=2"
+        );
+        assert_eq!(
+            relative_path(
+                "http://127.0.0.1:8080/api/v2/",
+                &url("http://127.0.0.1:8080/api/v2/github/kitsuyui/repos/")
+            ),
+            "github/kitsuyui/repos"
+        );
+        assert_eq!(
+            relative_path(
+                "https://codecov.io/api/v2",
+                &url("https://codecov.io/other")
+            ),
+            "/other"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::author::Author;
+use crate::timestamp::Timestamp;
 use crate::url::Url;
 /**
  * Codecov v2 API
@@ -9,7 +10,7 @@ use serde::{Deserialize, Serialize};
 /**
  * ReposAPIResponse is a struct that represents the response from the repos API.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ReposAPIResponse {
     pub results: Vec<Repo>,
     pub count: usize,
@@ -21,11 +22,12 @@ pub struct ReposAPIResponse {
 /**
  * Repo is a struct that represents a repo.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Repo {
     pub name: String,
     pub private: bool,
-    pub updatestamp: String, // TODO: ISO Date
+    #[cfg_attr(feature = "chrono", serde(with = "crate::timestamp::serde_timestamp"))]
+    pub updatestamp: Timestamp,
     pub author: Author,
     pub language: Option<String>,
     pub branch: Option<String>,
@@ -0,0 +1,74 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::Error;
+
+/**
+ * Revision is either a branch name or a commit sha.
+ * When parsing, "branch:" and "commit:" prefixes select the kind explicitly
+ * (git does not allow ':' in ref names). Otherwise a 40 character hex string is a commit.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Revision {
+    Branch(String),
+    Commit(String),
+}
+
+impl FromStr for Revision {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let revision = if let Some(branch) = s.strip_prefix("branch:") {
+            Revision::Branch(branch.to_string())
+        } else if let Some(commit) = s.strip_prefix("commit:") {
+            Revision::Commit(commit.to_string())
+        } else if s.len() == 40 && s.chars().all(|c| c.is_ascii_hexdigit()) {
+            Revision::Commit(s.to_string())
+        } else {
+            Revision::Branch(s.to_string())
+        };
+        match &revision {
+            Revision::Branch(name) | Revision::Commit(name) if name.is_empty() => {
+                Err(Error::ParseError(format!("empty revision: {:?}", s)))
+            }
+            _ => Ok(revision),
+        }
+    }
+}
+
+impl fmt::Display for Revision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Revision::Branch(name) => write!(f, "branch:{}", name),
+            Revision::Commit(sha) => write!(f, "commit:{}", sha),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        let sha = "1eb341765e7c3daa88ae5d2a751538a620c6dbce";
+        assert_eq!(
+            sha.parse::<Revision>().unwrap(),
+            Revision::Commit(sha.to_string())
+        );
+        assert_eq!(
+            "feature/foo".parse::<Revision>().unwrap(),
+            Revision::Branch("feature/foo".to_string())
+        );
+        assert_eq!(
+            "commit:1eb3417".parse::<Revision>().unwrap(),
+            Revision::Commit("1eb3417".to_string())
+        );
+        assert_eq!(
+            "branch:main".parse::<Revision>().unwrap().to_string(),
+            "branch:main"
+        );
+        assert!("branch:".parse::<Revision>().is_err());
+        assert!("".parse::<Revision>().is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::errors::Error;
+
+/**
+ * Service is an enum that represents the git hosting service of an owner.
+ * Short aliases like "gh", "gl" and "bb" are accepted when parsing.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Service {
+    Github,
+    Gitlab,
+    Bitbucket,
+    GithubEnterprise,
+    GitlabEnterprise,
+    BitbucketServer,
+}
+
+impl Service {
+    /**
+     * Returns the name used by the API, e.g. "github".
+     */
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Service::Github => "github",
+            Service::Gitlab => "gitlab",
+            Service::Bitbucket => "bitbucket",
+            Service::GithubEnterprise => "github_enterprise",
+            Service::GitlabEnterprise => "gitlab_enterprise",
+            Service::BitbucketServer => "bitbucket_server",
+        }
+    }
+}
+
+impl FromStr for Service {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "github" | "gh" => Ok(Service::Github),
+            "gitlab" | "gl" => Ok(Service::Gitlab),
+            "bitbucket" | "bb" => Ok(Service::Bitbucket),
+            "github_enterprise" | "ghe" => Ok(Service::GithubEnterprise),
+            "gitlab_enterprise" | "gle" => Ok(Service::GitlabEnterprise),
+            "bitbucket_server" | "bbs" => Ok(Service::BitbucketServer),
+            _ => Err(Error::ParseError(format!("unknown service: {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for Service {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Service {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Service {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse::<Service>()
+            .map_err(|_| serde::de::Error::custom(format!("unknown service: {}", s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("github".parse::<Service>().unwrap(), Service::Github);
+        assert_eq!("gh".parse::<Service>().unwrap(), Service::Github);
+        assert_eq!("GL".parse::<Service>().unwrap(), Service::Gitlab);
+        assert_eq!("bb".parse::<Service>().unwrap(), Service::Bitbucket);
+        assert_eq!(
+            "bitbucket_server".parse::<Service>().unwrap(),
+            Service::BitbucketServer
+        );
+        assert!("gihtub".parse::<Service>().is_err());
+    }
+
+    #[test]
+    fn test_serde() {
+        let service: Service = serde_json::from_str("\"gh\"").unwrap();
+        assert_eq!(service, Service::Github);
+        assert_eq!(
+            serde_json::to_string(&Service::GithubEnterprise).unwrap(),
+            "\"github_enterprise\""
+        );
+        assert_eq!(Service::Gitlab.to_string(), "gitlab");
+        assert!(serde_json::from_str::<Service>("\"svn\"").is_err());
+    }
+}
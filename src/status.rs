@@ -0,0 +1,720 @@
+/**
+ * Offline evaluation of Codecov's project and patch statuses.
+ * https://docs.codecov.com/docs/commit-status
+ */
+use regex::Regex;
+
+use crate::config::{CodecovConfig, Round, StatusConfig, StatusType, Target, Toggle};
+use crate::coverage::CoverageReport;
+use crate::diff::{self, AddedLines};
+use crate::errors::Error;
+use crate::path_fixer::path_matches;
+use crate::totals::Totals;
+
+const DEFAULT_PRECISION: u8 = 2;
+
+/**
+ * CoverageSource is the coverage of a commit, either totals from the API or a parsed report.
+ * Flags, paths and patch statuses need a parsed report.
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum CoverageSource<'a> {
+    Totals(&'a Totals),
+    Report(&'a CoverageReport),
+}
+
+/**
+ * StatusInput is a struct that holds everything needed to evaluate statuses.
+ * diff is a unified diff between base and head.
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct StatusInput<'a> {
+    pub base: Option<CoverageSource<'a>>,
+    pub head: CoverageSource<'a>,
+    pub diff: Option<&'a str>,
+}
+
+/**
+ * StatusState is an enum that represents the outcome of a status.
+ * Error means the status could not be evaluated with the given input.
+ * Skipped means the status type is not supported offline (changes statuses) and never fails.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusState {
+    Success,
+    Failure,
+    Error,
+    Skipped,
+}
+
+/**
+ * StatusResult is a struct that represents the outcome of a single status.
+ * context is the name Codecov would post, e.g. "codecov/project" or "codecov/patch/core".
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusResult {
+    pub context: String,
+    pub status_type: StatusType,
+    pub state: StatusState,
+    pub informational: bool,
+    pub coverage: Option<f64>,
+    pub target: Option<f64>,
+    pub flags: Vec<String>,
+    pub component: Option<String>,
+    pub reason: String,
+}
+
+/**
+ * Verdict is a struct that holds the results of all statuses.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Verdict {
+    pub statuses: Vec<StatusResult>,
+}
+
+impl Verdict {
+    /**
+     * Returns true if no status failed. Informational statuses never fail the verdict.
+     */
+    pub fn passed(&self) -> bool {
+        self.failures().is_empty()
+    }
+
+    /**
+     * Returns the statuses that make the verdict fail.
+     */
+    pub fn failures(&self) -> Vec<&StatusResult> {
+        self.statuses
+            .iter()
+            .filter(|status| {
+                !status.informational
+                    && matches!(status.state, StatusState::Failure | StatusState::Error)
+            })
+            .collect()
+    }
+}
+
+/**
+ * Rule is a status definition normalized from the different places codecov.yml allows them.
+ */
+struct Rule {
+    context: String,
+    status_type: StatusType,
+    target: Target,
+    threshold: f64,
+    informational: bool,
+    flags: Vec<String>,
+    flag_regexes: Vec<Regex>,
+    paths: Vec<String>,
+    component: Option<String>,
+}
+
+/**
+ * Settings is a struct that holds the rounding settings of coverage.
+ */
+struct Settings {
+    precision: u8,
+    round: Round,
+}
+
+impl Settings {
+    fn round(&self, value: f64) -> f64 {
+        let factor = 10f64.powi(i32::from(self.precision));
+        let scaled = value * factor;
+        // Avoid 59.999999 being rounded down because of floating point errors.
+        let scaled = if (scaled - scaled.round()).abs() < 1e-6 {
+            scaled.round()
+        } else {
+            scaled
+        };
+        let rounded = match self.round {
+            Round::Down => scaled.floor(),
+            Round::Up => scaled.ceil(),
+            Round::Nearest => scaled.round(),
+        };
+        rounded / factor
+    }
+}
+
+/**
+ * Evaluates every project and patch status configured in codecov.yml.
+ * Without a coverage.status section, Codecov's defaults (project and patch with target auto) are used.
+ */
+pub fn evaluate(config: &CodecovConfig, input: &StatusInput) -> Result<Verdict, Error> {
+    let settings = Settings {
+        precision: config
+            .coverage
+            .as_ref()
+            .and_then(|c| c.precision)
+            .unwrap_or(DEFAULT_PRECISION),
+        round: config
+            .coverage
+            .as_ref()
+            .and_then(|c| c.round)
+            .unwrap_or(Round::Down),
+    };
+    let added = input.diff.map(diff::added_lines);
+    let mut verdict = Verdict::default();
+    for rule in rules(config)? {
+        verdict
+            .statuses
+            .push(evaluate_rule(&rule, input, added.as_ref(), &settings));
+    }
+    Ok(verdict)
+}
+
+fn rules(config: &CodecovConfig) -> Result<Vec<Rule>, Error> {
+    let mut rules = Vec::new();
+    let status = config.coverage.as_ref().and_then(|c| c.status.as_ref());
+    let status_config = status.and_then(Toggle::config);
+    if status.map(Toggle::is_enabled).unwrap_or(true) {
+        let project = status_config.and_then(|s| s.project.as_ref());
+        let patch = status_config.and_then(|s| s.patch.as_ref());
+        let changes = status_config.and_then(|s| s.changes.as_ref());
+        for (status_type, setting) in [
+            (StatusType::Project, project),
+            (StatusType::Patch, patch),
+            (StatusType::Changes, changes),
+        ] {
+            match setting {
+                Some(Toggle::Config(statuses)) => {
+                    for (name, status) in statuses {
+                        rules.push(classic_rule(status_type, name, status));
+                    }
+                }
+                Some(Toggle::Enabled(false)) => {}
+                // Changes statuses are off unless configured.
+                None if status_type == StatusType::Changes => {}
+                Some(Toggle::Enabled(true)) | None => {
+                    rules.push(classic_rule(
+                        status_type,
+                        "default",
+                        &StatusConfig::default(),
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(management) = &config.flag_management {
+        let defaults = management.default_rules.as_ref();
+        for flag in &management.individual_flags {
+            let name = match &flag.name {
+                Some(name) => name,
+                None => continue,
+            };
+            let statuses = if flag.statuses.is_empty() {
+                defaults.map(|d| d.statuses.as_slice()).unwrap_or(&[])
+            } else {
+                flag.statuses.as_slice()
+            };
+            let paths = if flag.paths.is_empty() {
+                defaults.map(|d| d.paths.clone()).unwrap_or_default()
+            } else {
+                flag.paths.clone()
+            };
+            for status in statuses {
+                rules.push(Rule {
+                    context: context(status.status_type, status.name_prefix.as_deref(), name),
+                    status_type: status.status_type,
+                    target: status.target.unwrap_or(Target::Auto),
+                    threshold: status.threshold.map(|t| t.0).unwrap_or(0.0),
+                    informational: status.informational.unwrap_or(false),
+                    flags: vec![name.clone()],
+                    flag_regexes: vec![],
+                    paths: paths.clone(),
+                    component: None,
+                });
+            }
+        }
+    }
+
+    if let Some(management) = &config.component_management {
+        let defaults = management.default_rules.as_ref();
+        for component in &management.individual_components {
+            let id = match component.component_id.as_ref().or(component.name.as_ref()) {
+                Some(id) => id,
+                None => continue,
+            };
+            let statuses = if component.statuses.is_empty() {
+                defaults.map(|d| d.statuses.as_slice()).unwrap_or(&[])
+            } else {
+                component.statuses.as_slice()
+            };
+            let paths = if component.paths.is_empty() {
+                defaults.map(|d| d.paths.clone()).unwrap_or_default()
+            } else {
+                component.paths.clone()
+            };
+            let flag_regexes = if component.flag_regexes.is_empty() {
+                defaults.map(|d| d.flag_regexes.clone()).unwrap_or_default()
+            } else {
+                component.flag_regexes.clone()
+            };
+            let flag_regexes = compile_regexes(&flag_regexes)?;
+            for status in statuses {
+                rules.push(Rule {
+                    context: context(status.status_type, status.name_prefix.as_deref(), id),
+                    status_type: status.status_type,
+                    target: status.target.unwrap_or(Target::Auto),
+                    threshold: status.threshold.map(|t| t.0).unwrap_or(0.0),
+                    informational: status.informational.unwrap_or(false),
+                    flags: vec![],
+                    flag_regexes: flag_regexes.clone(),
+                    paths: paths.clone(),
+                    component: Some(id.clone()),
+                });
+            }
+        }
+    }
+    Ok(rules)
+}
+
+fn classic_rule(status_type: StatusType, name: &str, status: &StatusConfig) -> Rule {
+    let context = match name {
+        "default" => format!("codecov/{}", type_name(status_type)),
+        _ => context(status_type, None, name),
+    };
+    Rule {
+        context,
+        status_type,
+        target: status.target.unwrap_or(Target::Auto),
+        threshold: status.threshold.map(|t| t.0).unwrap_or(0.0),
+        informational: status.informational.unwrap_or(false),
+        flags: status.flags.clone(),
+        flag_regexes: vec![],
+        paths: status.paths.clone(),
+        component: None,
+    }
+}
+
+fn context(status_type: StatusType, prefix: Option<&str>, name: &str) -> String {
+    format!(
+        "codecov/{}/{}{}",
+        type_name(status_type),
+        prefix.unwrap_or(""),
+        name
+    )
+}
+
+fn type_name(status_type: StatusType) -> &'static str {
+    match status_type {
+        StatusType::Project => "project",
+        StatusType::Patch => "patch",
+        StatusType::Changes => "changes",
+    }
+}
+
+fn compile_regexes(patterns: &[String]) -> Result<Vec<Regex>, Error> {
+    patterns
+        .iter()
+        .map(|pattern| match Regex::new(pattern) {
+            Ok(regex) => Ok(regex),
+            Err(e) => Err(Error::ConfigError(format!(
+                "invalid flag regex {:?}: {}",
+                pattern, e
+            ))),
+        })
+        .collect()
+}
+
+impl Rule {
+    fn is_scoped(&self) -> bool {
+        !self.flags.is_empty() || !self.flag_regexes.is_empty() || !self.paths.is_empty()
+    }
+
+    /**
+     * Returns the part of a report this rule looks at.
+     */
+    fn scope(&self, report: &CoverageReport) -> CoverageReport {
+        let mut scoped = if self.flags.is_empty() && self.flag_regexes.is_empty() {
+            report.clone()
+        } else {
+            let mut flags: Vec<&str> = self.flags.iter().map(String::as_str).collect();
+            for session in &report.sessions {
+                for flag in &session.flags {
+                    if self.flag_regexes.iter().any(|r| r.is_match(flag)) {
+                        flags.push(flag);
+                    }
+                }
+            }
+            report.filter_flags(&flags)
+        };
+        if !self.paths.is_empty() {
+            let (excludes, includes): (Vec<&String>, Vec<&String>) =
+                self.paths.iter().partition(|p| p.starts_with('!'));
+            scoped.files.retain(|name, _| {
+                let included =
+                    includes.is_empty() || includes.iter().any(|p| path_matches(p, name));
+                let excluded = excludes.iter().any(|p| path_matches(&p[1..], name));
+                included && !excluded
+            });
+        }
+        scoped
+    }
+
+    /**
+     * Returns the project coverage of a source within this rule's scope.
+     * Ok(None) means there are no lines in scope.
+     */
+    fn project_coverage(&self, source: &CoverageSource) -> Result<Option<f64>, String> {
+        match source {
+            CoverageSource::Totals(totals) => {
+                if self.is_scoped() {
+                    return Err("flags and paths need a parsed coverage report".to_string());
+                }
+                Ok(Some(totals.coverage))
+            }
+            CoverageSource::Report(report) => {
+                let totals = self.scope(report).totals();
+                match totals.lines {
+                    0 => Ok(None),
+                    _ => Ok(Some(totals.coverage)),
+                }
+            }
+        }
+    }
+
+    /**
+     * Returns the coverage of the added lines within this rule's scope.
+     */
+    fn patch_coverage(
+        &self,
+        source: &CoverageSource,
+        added: Option<&AddedLines>,
+    ) -> Result<Option<f64>, String> {
+        let report = match source {
+            CoverageSource::Report(report) => report,
+            CoverageSource::Totals(_) => {
+                return Err("patch status needs a parsed coverage report".to_string())
+            }
+        };
+        let added = match added {
+            Some(added) => added,
+            None => return Err("patch status needs a diff".to_string()),
+        };
+        let totals = diff::patch_totals(&self.scope(report), added);
+        match totals.lines {
+            0 => Ok(None),
+            _ => Ok(Some(totals.coverage)),
+        }
+    }
+}
+
+fn evaluate_rule(
+    rule: &Rule,
+    input: &StatusInput,
+    added: Option<&AddedLines>,
+    settings: &Settings,
+) -> StatusResult {
+    let mut result = StatusResult {
+        context: rule.context.clone(),
+        status_type: rule.status_type,
+        state: StatusState::Success,
+        informational: rule.informational,
+        coverage: None,
+        target: None,
+        flags: rule.flags.clone(),
+        component: rule.component.clone(),
+        reason: String::new(),
+    };
+
+    let head = match rule.status_type {
+        StatusType::Project => rule.project_coverage(&input.head),
+        StatusType::Patch => rule.patch_coverage(&input.head, added),
+        StatusType::Changes => {
+            result.state = StatusState::Skipped;
+            result.reason = "changes statuses are not supported".to_string();
+            return result;
+        }
+    };
+    let head = match head {
+        Ok(Some(head)) => settings.round(head),
+        Ok(None) => {
+            result.reason = match rule.status_type {
+                StatusType::Patch => "no coverable lines were changed".to_string(),
+                _ => "no coverage data in scope".to_string(),
+            };
+            return result;
+        }
+        Err(reason) => {
+            result.state = StatusState::Error;
+            result.reason = reason;
+            return result;
+        }
+    };
+    result.coverage = Some(head);
+
+    let target = match rule.target {
+        Target::Percent(target) => target,
+        Target::Auto => {
+            let base = match &input.base {
+                Some(base) => rule.project_coverage(base),
+                None => Ok(None),
+            };
+            match base {
+                Ok(Some(base)) => settings.round(base),
+                Ok(None) => {
+                    result.reason = "no base coverage to compare with".to_string();
+                    return result;
+                }
+                Err(reason) => {
+                    result.state = StatusState::Error;
+                    result.reason = reason;
+                    return result;
+                }
+            }
+        }
+    };
+    result.target = Some(target);
+
+    let minimum = target - rule.threshold;
+    if head + 1e-9 >= minimum {
+        result.reason = format!("{}% (target {}%)", head, target);
+    } else {
+        result.state = StatusState::Failure;
+        result.reason = match rule.threshold {
+            t if t > 0.0 => format!("{}% is below target {}% (threshold {}%)", head, target, t),
+            _ => format!("{}% is below target {}%", head, target),
+        };
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coverage::{FileCoverage, LineCoverage, Session};
+    use crate::merge::{merge_sessions, MergeStrategy};
+    use crate::totals::Diff;
+
+    fn file(lines: &[(u32, bool)]) -> FileCoverage {
+        let mut file = FileCoverage::default();
+        for (line, hit) in lines {
+            let coverage = if *hit {
+                LineCoverage::Hit(1)
+            } else {
+                LineCoverage::Miss
+            };
+            file.lines.insert(*line, coverage);
+        }
+        file
+    }
+
+    fn totals(coverage: f64) -> Totals {
+        Totals {
+            files: 1,
+            lines: 100,
+            hits: 0,
+            misses: 0,
+            partials: 0,
+            coverage,
+            branches: 0,
+            methods: 0,
+            sessions: 1,
+            complexity: 0.0,
+            complexity_total: 0.0,
+            complexity_ratio: 0.0,
+            messages: 0,
+            diff: Diff::Value(0),
+        }
+    }
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,2 +1,4 @@
+ fn a() {}
++fn b() {}
++fn c() {}
+ fn d() {}
+";
+
+    #[test]
+    fn test_default_statuses() {
+        let config = CodecovConfig::default();
+        let base = totals(80.0);
+        let mut head = CoverageReport::default();
+        head.files.insert(
+            "src/lib.rs".to_string(),
+            file(&[(1, true), (2, true), (3, false), (4, true)]),
+        );
+        let input = StatusInput {
+            base: Some(CoverageSource::Totals(&base)),
+            head: CoverageSource::Report(&head),
+            diff: Some(DIFF),
+        };
+        let verdict = evaluate(&config, &input).unwrap();
+        assert_eq!(verdict.statuses.len(), 2);
+        let project = &verdict.statuses[0];
+        assert_eq!(project.context, "codecov/project");
+        assert_eq!(project.state, StatusState::Failure);
+        assert_eq!(project.coverage, Some(75.0));
+        assert_eq!(project.target, Some(80.0));
+        let patch = &verdict.statuses[1];
+        assert_eq!(patch.context, "codecov/patch");
+        assert_eq!(patch.coverage, Some(50.0));
+        assert_eq!(patch.state, StatusState::Failure);
+        assert!(!verdict.passed());
+    }
+
+    #[test]
+    fn test_configured_statuses() {
+        let config = CodecovConfig::from_yaml(
+            r#"
+coverage:
+  status:
+    project:
+      default:
+        target: auto
+        threshold: 10%
+      unit:
+        target: 90%
+        flags: [unit]
+    patch:
+      default:
+        target: 50%
+        informational: true
+component_management:
+  individual_components:
+    - component_id: core
+      paths: ["src/core/**"]
+      statuses:
+        - type: project
+          target: 60%
+"#,
+        )
+        .unwrap();
+        let mut unit = CoverageReport::default();
+        unit.files
+            .insert("src/lib.rs".to_string(), file(&[(1, true), (2, true)]));
+        let mut integration = CoverageReport::default();
+        integration.files.insert(
+            "src/core/a.rs".to_string(),
+            file(&[(1, true), (2, false), (3, false)]),
+        );
+        let head = merge_sessions(
+            vec![
+                (Session::new("unit", &["unit"]), unit),
+                (Session::new("integration", &["integration"]), integration),
+            ],
+            MergeStrategy::Sum,
+        );
+        let base = totals(66.0);
+        let input = StatusInput {
+            base: Some(CoverageSource::Totals(&base)),
+            head: CoverageSource::Report(&head),
+            diff: None,
+        };
+        let verdict = evaluate(&config, &input).unwrap();
+        let contexts: Vec<&str> = verdict
+            .statuses
+            .iter()
+            .map(|s| s.context.as_str())
+            .collect();
+        assert_eq!(
+            contexts,
+            vec![
+                "codecov/project",
+                "codecov/project/unit",
+                "codecov/patch",
+                "codecov/project/core"
+            ]
+        );
+        // 3/5 = 60% against auto target 66% with 10% threshold.
+        assert_eq!(verdict.statuses[0].state, StatusState::Success);
+        assert_eq!(verdict.statuses[1].coverage, Some(100.0));
+        assert_eq!(verdict.statuses[1].state, StatusState::Success);
+        // No diff for the patch status, but it is informational.
+        assert_eq!(verdict.statuses[2].state, StatusState::Error);
+        assert!(verdict.statuses[2].informational);
+        assert_eq!(verdict.statuses[3].component.as_deref(), Some("core"));
+        assert_eq!(verdict.statuses[3].coverage, Some(33.33));
+        assert_eq!(verdict.statuses[3].state, StatusState::Failure);
+        assert_eq!(verdict.failures().len(), 1);
+    }
+
+    #[test]
+    fn test_totals_only() {
+        let config = CodecovConfig::from_yaml("coverage:\n  status:\n    patch: off\n").unwrap();
+        let base = totals(80.0);
+        let head = totals(80.5);
+        let input = StatusInput {
+            base: Some(CoverageSource::Totals(&base)),
+            head: CoverageSource::Totals(&head),
+            diff: None,
+        };
+        let verdict = evaluate(&config, &input).unwrap();
+        assert_eq!(verdict.statuses.len(), 1);
+        assert!(verdict.passed());
+    }
+
+    #[test]
+    fn test_classic_changes_status_is_skipped() {
+        let config = CodecovConfig::from_yaml(
+            r#"
+coverage:
+  status:
+    patch: off
+    changes:
+      default:
+        informational: true
+"#,
+        )
+        .unwrap();
+        let head = totals(10.0);
+        let input = StatusInput {
+            base: None,
+            head: CoverageSource::Totals(&head),
+            diff: None,
+        };
+        let verdict = evaluate(&config, &input).unwrap();
+        assert_eq!(verdict.statuses.len(), 2);
+        let changes = &verdict.statuses[1];
+        assert_eq!(changes.context, "codecov/changes");
+        assert_eq!(changes.state, StatusState::Skipped);
+        assert_eq!(changes.reason, "changes statuses are not supported");
+
+        let config = CodecovConfig::from_yaml("coverage:\n  status:\n    changes: true\n").unwrap();
+        let verdict = evaluate(&config, &input).unwrap();
+        assert_eq!(verdict.statuses[2].context, "codecov/changes");
+        assert_eq!(verdict.statuses[2].state, StatusState::Skipped);
+
+        let verdict = evaluate(&CodecovConfig::default(), &input).unwrap();
+        assert!(verdict
+            .statuses
+            .iter()
+            .all(|s| s.status_type != StatusType::Changes));
+    }
+
+    #[test]
+    fn test_changes_status_is_skipped() {
+        let config = CodecovConfig::from_yaml(
+            r#"
+coverage:
+  status:
+    patch: off
+component_management:
+  individual_components:
+    - component_id: core
+      paths: ["src/**"]
+      statuses:
+        - type: changes
+"#,
+        )
+        .unwrap();
+        let head = totals(10.0);
+        let input = StatusInput {
+            base: None,
+            head: CoverageSource::Totals(&head),
+            diff: None,
+        };
+        let verdict = evaluate(&config, &input).unwrap();
+        let changes = &verdict.statuses[1];
+        assert_eq!(changes.context, "codecov/changes/core");
+        assert_eq!(changes.state, StatusState::Skipped);
+        assert_eq!(changes.coverage, None);
+        assert!(verdict.passed());
+    }
+}
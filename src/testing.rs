@@ -0,0 +1,1084 @@
+/**
+ * An in-process fake of the Codecov v2 API, so that code using Client can be tested offline.
+ * It is enabled by the `testing` feature.
+ *
+ * The server listens on 127.0.0.1 with a random port, paginates lists like the API
+ * (page and page_size query parameters, absolute next/previous links) and answers
+ * unknown resources with 404 and wrong tokens with 401.
+ *
+ * ```
+ * use codecov::author::Author;
+ * use codecov::testing::{FakeCommit, FakeServer};
+ * use codecov::totals::Totals;
+ *
+ * let server = FakeServer::start().unwrap();
+ * let author: Author = "github/kitsuyui/rust-codecov".parse().unwrap();
+ * server.add_repo(&author);
+ * server.add_commit(
+ *     &author,
+ *     FakeCommit::new("1eb341765e7c3daa88ae5d2a751538a620c6dbce", "main", "2023-08-01T15:41:47Z")
+ *         .with_file("src/lib.rs", Totals { lines: 10, hits: 9, misses: 1, coverage: 90.0, ..Default::default() }),
+ * );
+ * let client = server.client();
+ * let detail = client.get_branch_detail(&author, "main").unwrap();
+ * assert_eq!(detail.latest_coverage(), 90.0);
+ * ```
+ */
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+#[cfg(feature = "async")]
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+use std::future::{ready, Future};
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+#[cfg(feature = "async")]
+use crate::api::AsyncCodecovApi;
+use crate::api::CodecovApi;
+use crate::author::Author;
+use crate::branch_detail::BranchDetailAPIResponse;
+#[cfg(feature = "async")]
+use crate::branch_detail::Report;
+use crate::branches::BranchesAPIResponse;
+use crate::cache::fnv1a;
+use crate::commits::{Commit, CommitHistory, CommitState, CommitsAPIResponse};
+use crate::errors::Error;
+#[cfg(feature = "async")]
+use crate::gate::{Gate, GateResult};
+use crate::owner::Owner;
+use crate::repos::Repo;
+#[cfg(feature = "async")]
+use crate::revision::Revision;
+use crate::service::Service;
+use crate::totals::Totals;
+use crate::uploads::Upload;
+use crate::url::api_url;
+#[cfg(feature = "async")]
+use crate::wait::WaitOptions;
+use crate::{check_status, Client};
+
+/**
+ * FAKE_TOKEN is the token FakeServer accepts unless another one is set with FakeServer::set_token.
+ */
+pub const FAKE_TOKEN: &str = "fake-token";
+
+/**
+ * DEFAULT_PAGE_SIZE is the page size used when a request has no page_size parameter.
+ */
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+
+/**
+ * FakeCommit is a commit served by FakeServer.
+ * The totals of the commit are the sum of its files unless set with with_totals.
+ */
+#[derive(Debug, Clone)]
+pub struct FakeCommit {
+    pub commitid: String,
+    pub branch: String,
+    pub message: String,
+    pub timestamp: String,
+    pub state: CommitState,
+    pub parent: Option<String>,
+    pub totals: Option<Totals>,
+    pub files: Vec<(String, Totals)>,
+    pub uploads: Vec<Upload>,
+}
+
+impl FakeCommit {
+    pub fn new(commitid: &str, branch: &str, timestamp: &str) -> FakeCommit {
+        FakeCommit {
+            commitid: commitid.to_string(),
+            branch: branch.to_string(),
+            message: format!("commit {}", commitid),
+            timestamp: timestamp.to_string(),
+            state: CommitState::Complete,
+            parent: None,
+            totals: None,
+            files: vec![],
+            uploads: vec![],
+        }
+    }
+
+    pub fn with_message(mut self, message: &str) -> FakeCommit {
+        self.message = message.to_string();
+        self
+    }
+
+    pub fn with_state(mut self, state: CommitState) -> FakeCommit {
+        self.state = state;
+        self
+    }
+
+    pub fn with_parent(mut self, parent: &str) -> FakeCommit {
+        self.parent = Some(parent.to_string());
+        self
+    }
+
+    pub fn with_totals(mut self, totals: Totals) -> FakeCommit {
+        self.totals = Some(totals);
+        self
+    }
+
+    pub fn with_file(mut self, name: &str, totals: Totals) -> FakeCommit {
+        self.files.push((name.to_string(), totals));
+        self
+    }
+
+    pub fn with_upload(mut self, upload: Upload) -> FakeCommit {
+        self.uploads.push(upload);
+        self
+    }
+
+    fn totals(&self) -> Totals {
+        if let Some(totals) = &self.totals {
+            return totals.clone();
+        }
+        let mut totals = Totals {
+            files: self.files.len(),
+            sessions: self.uploads.len(),
+            ..Default::default()
+        };
+        for (_, file) in &self.files {
+            totals.lines += file.lines;
+            totals.hits += file.hits;
+            totals.misses += file.misses;
+            totals.partials += file.partials;
+            totals.branches += file.branches;
+            totals.methods += file.methods;
+        }
+        if totals.lines > 0 {
+            totals.coverage = totals.hits as f64 / totals.lines as f64 * 100.0;
+        }
+        totals
+    }
+}
+
+/**
+ * FakeServer is an in-process fake Codecov server. It stops when dropped.
+ */
+pub struct FakeServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+struct FakeRepo {
+    service: Service,
+    username: String,
+    name: String,
+    private: bool,
+    language: Option<String>,
+    commits: Vec<FakeCommit>,
+}
+
+struct State {
+    token: String,
+    repos: Vec<FakeRepo>,
+    responses: BTreeMap<String, (u16, Value)>,
+    requests: Vec<String>,
+    statuses: Vec<u16>,
+}
+
+impl State {
+    fn new() -> State {
+        State {
+            token: FAKE_TOKEN.to_string(),
+            repos: vec![],
+            responses: BTreeMap::new(),
+            requests: vec![],
+            statuses: vec![],
+        }
+    }
+
+    fn add_repo(&mut self, author: &Author) {
+        if find_repo(
+            &mut self.repos,
+            author.service,
+            &author.username,
+            &author.name,
+        )
+        .is_some()
+        {
+            return;
+        }
+        self.repos.push(FakeRepo {
+            service: author.service,
+            username: author.username.clone(),
+            name: author.name.clone(),
+            private: false,
+            language: Some("rust".to_string()),
+            commits: vec![],
+        });
+    }
+
+    fn add_commit(&mut self, author: &Author, commit: FakeCommit) {
+        self.add_repo(author);
+        if let Some(repo) = find_repo(
+            &mut self.repos,
+            author.service,
+            &author.username,
+            &author.name,
+        ) {
+            repo.commits.retain(|c| c.commitid != commit.commitid);
+            repo.commits.push(commit);
+        }
+    }
+}
+
+impl FakeServer {
+    /**
+     * Starts a server on 127.0.0.1 with a random port.
+     */
+    pub fn start() -> Result<FakeServer, Error> {
+        let listener = match TcpListener::bind("127.0.0.1:0") {
+            Ok(listener) => listener,
+            Err(e) => return Err(Error::IoError(e)),
+        };
+        let addr = match listener.local_addr() {
+            Ok(addr) => addr,
+            Err(e) => return Err(Error::IoError(e)),
+        };
+        let state = Arc::new(Mutex::new(State::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let state = state.clone();
+            let stop = stop.clone();
+            let base = format!("http://{}", addr);
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        // A broken connection only affects the client that made it.
+                        let _ = handle_connection(stream, &state, &base);
+                    }
+                }
+            })
+        };
+        Ok(FakeServer {
+            addr,
+            state,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /**
+     * Returns the base URL of the API, to be passed to Client::with_api_endpoint.
+     */
+    pub fn api_endpoint(&self) -> String {
+        format!("http://{}/api/v2", self.addr)
+    }
+
+    /**
+     * Returns a Client that talks to this server with the accepted token.
+     */
+    pub fn client(&self) -> Client {
+        let token = lock(&self.state).token.clone();
+        Client::new(token).with_api_endpoint(&self.api_endpoint())
+    }
+
+    /**
+     * Sets the token the server accepts. Requests with another token get 401.
+     */
+    pub fn set_token(&self, token: &str) {
+        lock(&self.state).token = token.to_string();
+    }
+
+    /**
+     * Adds a repo. Adding the same repo again does nothing.
+     */
+    pub fn add_repo(&self, author: &Author) {
+        lock(&self.state).add_repo(author);
+    }
+
+    /**
+     * Adds a commit to a repo, adding the repo if needed.
+     * The last commit added to a branch is its head.
+     */
+    pub fn add_commit(&self, author: &Author, commit: FakeCommit) {
+        lock(&self.state).add_commit(author, commit);
+    }
+
+    /**
+     * Serves a fixed response for a path (e.g. "/api/v2/github/kitsuyui/repos"), ignoring the query.
+     * Useful to simulate errors like 500 or malformed payloads.
+     */
+    pub fn respond_with(&self, path: &str, status: u16, body: Value) {
+        lock(&self.state)
+            .responses
+            .insert(path.trim_end_matches('/').to_string(), (status, body));
+    }
+
+    /**
+     * Returns the paths and queries of the requests received so far.
+     */
+    pub fn requests(&self) -> Vec<String> {
+        lock(&self.state).requests.clone()
+    }
+
+    /**
+     * Returns the statuses of the responses sent so far, e.g. 304 for revalidated cache entries.
+     */
+    pub fn statuses(&self) -> Vec<u16> {
+        lock(&self.state).statuses.clone()
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the accept loop up so that it sees the stop flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/**
+ * MockApi is an in-memory implementation of CodecovApi with the same data model as FakeServer,
+ * for code that is generic over the backend and does not need a Client.
+ * Paginated operations return all results in one page.
+ */
+pub struct MockApi {
+    state: Mutex<State>,
+}
+
+impl Default for MockApi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockApi {
+    pub fn new() -> MockApi {
+        MockApi {
+            state: Mutex::new(State::new()),
+        }
+    }
+
+    /**
+     * Adds a repo. Adding the same repo again does nothing.
+     */
+    pub fn with_repo(self, author: &Author) -> MockApi {
+        lock(&self.state).add_repo(author);
+        self
+    }
+
+    /**
+     * Adds a commit to a repo, adding the repo if needed.
+     * The last commit added to a branch is its head.
+     */
+    pub fn with_commit(self, author: &Author, commit: FakeCommit) -> MockApi {
+        lock(&self.state).add_commit(author, commit);
+        self
+    }
+
+    /**
+     * Serves a fixed response for a path like FakeServer::respond_with.
+     * Error statuses are mapped like Client does: Error::HttpError for statuses other than 404,
+     * and Error::NotFoundError with the detail of the body for 404.
+     */
+    pub fn with_response(self, path: &str, status: u16, body: Value) -> MockApi {
+        lock(&self.state)
+            .responses
+            .insert(path.trim_end_matches('/').to_string(), (status, body));
+        self
+    }
+
+    /**
+     * Returns the paths and queries of the calls made so far.
+     */
+    pub fn requests(&self) -> Vec<String> {
+        lock(&self.state).requests.clone()
+    }
+
+    fn fetch(&self, segments: &[&str]) -> Result<(u16, Value), Error> {
+        let url = api_url(segments)?;
+        let target = format!("{}?page_size={}", url.path(), usize::MAX);
+        let mut state = lock(&self.state);
+        let authorization = format!("bearer {}", state.token);
+        Ok(respond(&mut state, &target, Some(&authorization), ""))
+    }
+
+    fn get<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T, Error> {
+        match self.fetch(segments)? {
+            (404, body) => Err(Error::NotFoundError(
+                body["detail"].as_str().unwrap_or("Not found.").to_string(),
+            )),
+            (status, body) => deserialize(check_status(status, body)?),
+        }
+    }
+
+    fn get_list<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<Vec<T>, Error> {
+        let page: Value = self.get(segments)?;
+        deserialize(page["results"].clone())
+    }
+}
+
+fn deserialize<T: DeserializeOwned>(value: Value) -> Result<T, Error> {
+    match serde_json::from_value(value) {
+        Ok(value) => Ok(value),
+        Err(e) => Err(Error::DeserializeError(e)),
+    }
+}
+
+impl CodecovApi for MockApi {
+    fn get_all_repos(&self, owner: &Owner) -> Result<Vec<Repo>, Error> {
+        self.get_list(&[owner.service.as_str(), &owner.username, "repos"])
+    }
+
+    fn get_commits(&self, author: &Author) -> Result<CommitsAPIResponse, Error> {
+        self.get(&[
+            author.service.as_str(),
+            &author.username,
+            "repos",
+            &author.name,
+            "commits",
+        ])
+    }
+
+    fn get_commit_history(&self, author: &Author) -> Result<CommitHistory, Error> {
+        Ok(CommitHistory::new(
+            CodecovApi::get_commits(self, author)?.results,
+        ))
+    }
+
+    fn get_commit(&self, author: &Author, commitid: &str) -> Result<Commit, Error> {
+        self.get(&[
+            author.service.as_str(),
+            &author.username,
+            "repos",
+            &author.name,
+            "commits",
+            commitid,
+        ])
+    }
+
+    fn get_commit_uploads(&self, author: &Author, commitid: &str) -> Result<Vec<Upload>, Error> {
+        self.get_list(&[
+            author.service.as_str(),
+            &author.username,
+            "repos",
+            &author.name,
+            "commits",
+            commitid,
+            "uploads",
+        ])
+    }
+
+    fn get_branches(&self, author: &Author) -> Result<BranchesAPIResponse, Error> {
+        self.get(&[
+            author.service.as_str(),
+            &author.username,
+            "repos",
+            &author.name,
+            "branches",
+        ])
+    }
+
+    fn get_branch_detail(
+        &self,
+        author: &Author,
+        branch_name: &str,
+    ) -> Result<BranchDetailAPIResponse, Error> {
+        // Like the API, a missing branch is a BranchDetailAPIResponse::NotFound rather than an error.
+        let (status, body) = self.fetch(&[
+            author.service.as_str(),
+            &author.username,
+            "repos",
+            &author.name,
+            "branches",
+            branch_name,
+        ])?;
+        deserialize(check_status(status, body)?)
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncCodecovApi for MockApi {
+    fn get_all_repos(
+        &self,
+        owner: &Owner,
+    ) -> impl Future<Output = Result<Vec<Repo>, Error>> + Send {
+        ready(CodecovApi::get_all_repos(self, owner))
+    }
+
+    fn get_commits(
+        &self,
+        author: &Author,
+    ) -> impl Future<Output = Result<CommitsAPIResponse, Error>> + Send {
+        ready(CodecovApi::get_commits(self, author))
+    }
+
+    fn get_commit_history(
+        &self,
+        author: &Author,
+    ) -> impl Future<Output = Result<CommitHistory, Error>> + Send {
+        ready(CodecovApi::get_commit_history(self, author))
+    }
+
+    fn get_commit(
+        &self,
+        author: &Author,
+        commitid: &str,
+    ) -> impl Future<Output = Result<Commit, Error>> + Send {
+        ready(CodecovApi::get_commit(self, author, commitid))
+    }
+
+    fn get_commit_uploads(
+        &self,
+        author: &Author,
+        commitid: &str,
+    ) -> impl Future<Output = Result<Vec<Upload>, Error>> + Send {
+        ready(CodecovApi::get_commit_uploads(self, author, commitid))
+    }
+
+    fn get_branches(
+        &self,
+        author: &Author,
+    ) -> impl Future<Output = Result<BranchesAPIResponse, Error>> + Send {
+        ready(CodecovApi::get_branches(self, author))
+    }
+
+    fn get_branch_detail(
+        &self,
+        author: &Author,
+        branch_name: &str,
+    ) -> impl Future<Output = Result<BranchDetailAPIResponse, Error>> + Send {
+        ready(CodecovApi::get_branch_detail(self, author, branch_name))
+    }
+
+    fn get_totals(
+        &self,
+        author: &Author,
+        revision: &Revision,
+    ) -> impl Future<Output = Result<Totals, Error>> + Send {
+        ready(CodecovApi::get_totals(self, author, revision))
+    }
+
+    fn get_report(
+        &self,
+        author: &Author,
+        revision: &Revision,
+    ) -> impl Future<Output = Result<Report, Error>> + Send {
+        ready(CodecovApi::get_report(self, author, revision))
+    }
+
+    fn check_gate(
+        &self,
+        gate: &Gate,
+        author: &Author,
+        head: &Revision,
+        base: Option<&Revision>,
+    ) -> impl Future<Output = Result<GateResult, Error>> + Send {
+        ready(CodecovApi::check_gate(self, gate, author, head, base))
+    }
+
+    fn wait_for_commit(
+        &self,
+        author: &Author,
+        commitid: &str,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Commit, Error>> + Send {
+        ready(CodecovApi::wait_for_commit(self, author, commitid, timeout))
+    }
+
+    fn wait_for_commit_with(
+        &self,
+        author: &Author,
+        commitid: &str,
+        options: &WaitOptions,
+    ) -> impl Future<Output = Result<Commit, Error>> + Send {
+        ready(CodecovApi::wait_for_commit_with(
+            self, author, commitid, options,
+        ))
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    match state.lock() {
+        Ok(state) => state,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+fn find_repo<'a>(
+    repos: &'a mut [FakeRepo],
+    service: Service,
+    username: &str,
+    name: &str,
+) -> Option<&'a mut FakeRepo> {
+    repos
+        .iter_mut()
+        .find(|repo| repo.service == service && repo.username == username && repo.name == name)
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    state: &Mutex<State>,
+    base: &str,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut authorization = None;
+    let mut if_none_match = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.trim().to_string());
+            } else if name.trim().eq_ignore_ascii_case("if-none-match") {
+                if_none_match = Some(value.trim().to_string());
+            }
+        }
+    }
+    let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let mut state = lock(state);
+    let (status, body) = respond(&mut state, target, authorization.as_deref(), base);
+    let body = body.to_string();
+    let etag = format!("\"{:016x}\"", fnv1a(body.as_bytes()));
+    let (status, body) = if status == 200 && if_none_match.as_deref() == Some(etag.as_str()) {
+        (304, String::new())
+    } else {
+        (status, body)
+    };
+    state.statuses.push(status);
+    drop(state);
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nETag: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        etag,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        304 => "Not Modified",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
+
+fn not_found() -> (u16, Value) {
+    (404, json!({"detail": "Not found."}))
+}
+
+fn respond(
+    state: &mut State,
+    target: &str,
+    authorization: Option<&str>,
+    base: &str,
+) -> (u16, Value) {
+    state.requests.push(target.to_string());
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let path = path.trim_end_matches('/');
+    if let Some(response) = state.responses.get(path) {
+        return response.clone();
+    }
+    let expected = format!("bearer {}", state.token);
+    if !authorization.is_some_and(|a| a.eq_ignore_ascii_case(&expected)) {
+        return (401, json!({"detail": "Invalid token."}));
+    }
+    let segments: Vec<String> = match path.strip_prefix("/api/v2/") {
+        Some(rest) => rest.split('/').map(percent_decode).collect(),
+        None => return not_found(),
+    };
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let (service, username, rest) = match segments.as_slice() {
+        [service, username, rest @ ..] => match service.parse::<Service>() {
+            Ok(service) => (service, *username, rest),
+            Err(_) => return not_found(),
+        },
+        _ => return not_found(),
+    };
+    let page = Page::from_query(query, base, path);
+    if rest == ["repos"] {
+        let repos: Vec<Value> = state
+            .repos
+            .iter()
+            .filter(|repo| repo.service == service && repo.username == username)
+            .map(repo_json)
+            .collect();
+        return (200, page.paginate(repos));
+    }
+    let (name, rest) = match rest {
+        ["repos", name, rest @ ..] => (*name, rest),
+        _ => return not_found(),
+    };
+    let repo = match find_repo(&mut state.repos, service, username, name) {
+        Some(repo) => repo,
+        None => return not_found(),
+    };
+    match rest {
+        [] => (200, repo_json(repo)),
+        ["branches"] => {
+            let branches: Vec<Value> = heads(repo)
+                .iter()
+                .map(|head| json!({"name": head.branch, "updatestamp": head.timestamp}))
+                .collect();
+            (200, page.paginate(branches))
+        }
+        ["branches", branch] => match heads(repo).into_iter().find(|head| head.branch == *branch) {
+            Some(head) => (
+                200,
+                json!({
+                    "name": head.branch,
+                    "updatestamp": head.timestamp,
+                    "head_commit": commit_json(repo, head, true),
+                }),
+            ),
+            None => not_found(),
+        },
+        ["commits"] => {
+            let commits: Vec<Value> = repo
+                .commits
+                .iter()
+                .rev()
+                .map(|commit| commit_json(repo, commit, false))
+                .collect();
+            (200, page.paginate(commits))
+        }
+        ["commits", commitid] => match find_commit(repo, commitid) {
+            Some(commit) => (200, commit_json(repo, commit, true)),
+            None => not_found(),
+        },
+        ["commits", commitid, "uploads"] => match find_commit(repo, commitid) {
+            Some(commit) => {
+                let uploads: Vec<Value> = commit
+                    .uploads
+                    .iter()
+                    .filter_map(|upload| serde_json::to_value(upload).ok())
+                    .collect();
+                (200, page.paginate(uploads))
+            }
+            None => not_found(),
+        },
+        _ => not_found(),
+    }
+}
+
+/**
+ * Returns the head commit of every branch, in the order the branches first appeared.
+ */
+fn heads(repo: &FakeRepo) -> Vec<&FakeCommit> {
+    let mut heads: Vec<&FakeCommit> = vec![];
+    for commit in &repo.commits {
+        match heads.iter_mut().find(|head| head.branch == commit.branch) {
+            Some(head) => *head = commit,
+            None => heads.push(commit),
+        }
+    }
+    heads
+}
+
+fn find_commit<'a>(repo: &'a FakeRepo, commitid: &str) -> Option<&'a FakeCommit> {
+    repo.commits
+        .iter()
+        .find(|commit| commit.commitid == commitid)
+}
+
+fn repo_json(repo: &FakeRepo) -> Value {
+    let updatestamp = repo
+        .commits
+        .last()
+        .map(|commit| commit.timestamp.clone())
+        .unwrap_or_else(|| "2023-01-01T00:00:00Z".to_string());
+    json!({
+        "name": repo.name,
+        "private": repo.private,
+        "updatestamp": updatestamp,
+        "author": {"service": repo.service, "username": repo.username, "name": repo.username},
+        "language": repo.language,
+        "branch": repo.commits.first().map(|commit| commit.branch.clone()),
+        "active": true,
+        "activated": true,
+    })
+}
+
+fn commit_json(repo: &FakeRepo, commit: &FakeCommit, with_report: bool) -> Value {
+    let totals = commit.totals();
+    let mut value = json!({
+        "commitid": commit.commitid,
+        "message": commit.message,
+        "timestamp": commit.timestamp,
+        "ci_passed": commit.state == CommitState::Complete,
+        "author": {"service": repo.service, "username": repo.username, "name": repo.username},
+        "branch": commit.branch,
+        "totals": totals,
+        "state": commit.state,
+        "parent": commit.parent,
+    });
+    if with_report {
+        let files: Vec<Value> = commit
+            .files
+            .iter()
+            .map(|(name, totals)| json!({"name": name, "totals": totals}))
+            .collect();
+        value["report"] = json!({"files": files, "totals": totals});
+    }
+    value
+}
+
+struct Page {
+    page: usize,
+    page_size: usize,
+    url: String,
+}
+
+impl Page {
+    fn from_query(query: &str, base: &str, path: &str) -> Page {
+        let mut page = Page {
+            page: 1,
+            page_size: DEFAULT_PAGE_SIZE,
+            url: format!("{}{}", base, path),
+        };
+        for pair in query.split('&') {
+            match pair.split_once('=') {
+                Some(("page", value)) => page.page = value.parse().unwrap_or(1).max(1),
+                Some(("page_size", value)) => {
+                    page.page_size = value.parse().unwrap_or(DEFAULT_PAGE_SIZE).max(1)
+                }
+                _ => {}
+            }
+        }
+        page
+    }
+
+    fn link(&self, page: usize) -> Value {
+        Value::from(format!(
+            "{}?page={}&page_size={}",
+            self.url, page, self.page_size
+        ))
+    }
+
+    fn paginate(&self, results: Vec<Value>) -> Value {
+        let count = results.len();
+        let total_pages = count.div_ceil(self.page_size).max(1);
+        let results: Vec<Value> = results
+            .into_iter()
+            .skip((self.page - 1) * self.page_size)
+            .take(self.page_size)
+            .collect();
+        let next = if self.page < total_pages {
+            self.link(self.page + 1)
+        } else {
+            Value::Null
+        };
+        let previous = if self.page > 1 {
+            self.link(self.page - 1)
+        } else {
+            Value::Null
+        };
+        json!({
+            "count": count,
+            "next": next,
+            "previous": previous,
+            "results": results,
+            "total_pages": total_pages,
+        })
+    }
+}
+
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("feature%2Ffoo%231"), "feature/foo#1");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+
+    #[test]
+    fn test_pagination() {
+        let server = FakeServer::start().unwrap();
+        let author: Author = "github/kitsuyui/rust-codecov".parse().unwrap();
+        for i in 0..45 {
+            server.add_commit(
+                &author,
+                FakeCommit::new(&format!("{:040x}", i), "main", "2023-08-01T15:41:47Z"),
+            );
+        }
+        let client = server.client();
+        let first = client.get_commits(&author).unwrap();
+        assert_eq!(first.count, 45);
+        assert_eq!(first.total_pages, 3);
+        assert_eq!(first.results.len(), DEFAULT_PAGE_SIZE);
+        assert_eq!(first.results[0].commitid, format!("{:040x}", 44));
+        assert!(first.next.is_some());
+        assert_eq!(
+            client.get_commit_history(&author).unwrap().commits.len(),
+            45
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let server = FakeServer::start().unwrap();
+        let author: Author = "github/kitsuyui/rust-codecov".parse().unwrap();
+        server.add_repo(&author);
+
+        let client = Client::new("wrong".to_string()).with_api_endpoint(&server.api_endpoint());
+        assert!(matches!(
+            client.get_branches(&author),
+            Err(Error::HttpError { status: 401, detail }) if detail == "Invalid token."
+        ));
+
+        let client = server.client();
+        assert!(matches!(
+            client.get_commit(&author, "missing"),
+            Err(Error::NotFoundError(_))
+        ));
+        server.respond_with(
+            "/api/v2/github/kitsuyui/repos/rust-codecov/branches",
+            500,
+            json!({"detail": "A server error occurred."}),
+        );
+        assert!(matches!(
+            client.get_branches(&author),
+            Err(Error::HttpError { status: 500, detail }) if detail == "A server error occurred."
+        ));
+        server.respond_with(
+            "/api/v2/github/kitsuyui/repos/rust-codecov/commits",
+            503,
+            json!("Service Unavailable"),
+        );
+        assert!(matches!(
+            client.get_commits(&author),
+            Err(Error::HttpError { status: 503, detail }) if detail == "\"Service Unavailable\""
+        ));
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[test]
+    fn test_mock_api() {
+        let author: Author = "github/kitsuyui/rust-codecov".parse().unwrap();
+        let totals = Totals {
+            lines: 10,
+            hits: 8,
+            misses: 2,
+            ..Default::default()
+        };
+        let mock = MockApi::new()
+            .with_commit(
+                &author,
+                FakeCommit::new(&format!("{:040x}", 1), "main", "2023-08-01T15:41:47Z")
+                    .with_file("src/lib.rs", totals.clone()),
+            )
+            .with_commit(
+                &author,
+                FakeCommit::new(
+                    &format!("{:040x}", 2),
+                    "feature/foo",
+                    "2023-08-02T15:41:47Z",
+                ),
+            );
+        let api: &dyn CodecovApi = &mock;
+        assert_eq!(api.get_all_repos(&author.to_owner()).unwrap().len(), 1);
+        assert_eq!(api.get_branches(&author).unwrap().count, 2);
+        assert_eq!(
+            api.get_totals(&author, &"main".parse().unwrap())
+                .unwrap()
+                .coverage,
+            80.0
+        );
+        assert!(matches!(
+            api.get_branch_detail(&author, "feature/foo").unwrap(),
+            BranchDetailAPIResponse::Success(_)
+        ));
+        assert!(matches!(
+            api.get_branch_detail(&author, "missing").unwrap(),
+            BranchDetailAPIResponse::NotFound(_)
+        ));
+        assert!(matches!(
+            api.get_commit(&author, "missing"),
+            Err(Error::NotFoundError(_))
+        ));
+        assert_eq!(api.get_commit_history(&author).unwrap().commits.len(), 2);
+        assert_eq!(mock.requests().len(), 7);
+
+        let sha = format!("{:040x}", 1);
+        let commit = api
+            .wait_for_commit(&author, &sha, std::time::Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(commit.commitid, sha);
+        let gate = crate::gate::Gate::new().with_target(90.0);
+        let result = api
+            .check_gate(&gate, &author, &"main".parse().unwrap(), None)
+            .unwrap();
+        assert!(!result.passed());
+    }
+
+    #[test]
+    fn test_mock_api_errors() {
+        let author: Author = "github/kitsuyui/rust-codecov".parse().unwrap();
+        let mock = MockApi::new()
+            .with_repo(&author)
+            .with_response(
+                "/api/v2/github/kitsuyui/repos/rust-codecov/commits",
+                401,
+                json!({"detail": "Invalid token."}),
+            )
+            .with_response(
+                "/api/v2/github/kitsuyui/repos/rust-codecov/branches/main",
+                500,
+                json!({"detail": "A server error occurred."}),
+            );
+        let api: &dyn CodecovApi = &mock;
+        assert!(matches!(
+            api.get_commits(&author),
+            Err(Error::HttpError { status: 401, detail }) if detail == "Invalid token."
+        ));
+        assert!(matches!(
+            api.get_branch_detail(&author, "main"),
+            Err(Error::HttpError { status: 500, detail }) if detail == "A server error occurred."
+        ));
+        assert!(matches!(
+            api.get_commit(&author, "missing"),
+            Err(Error::NotFoundError(_))
+        ));
+    }
+}
@@ -0,0 +1,103 @@
+/**
+ * Timestamp is the type of the timestamps returned by the API (updatestamp, timestamp, created_at, ...).
+ * With the `chrono` feature it is a chrono::DateTime<Utc>, so timestamps can be compared and
+ * filtered with chrono (e.g. the commits of the last 30 days); otherwise it is the raw ISO 8601 string.
+ * Both are ordered, so sorting and range filtering work either way,
+ * but comparing strings is only reliable when they share the same format and offset.
+ */
+#[cfg(feature = "chrono")]
+pub type Timestamp = chrono::DateTime<chrono::Utc>;
+
+#[cfg(not(feature = "chrono"))]
+pub type Timestamp = String;
+
+/**
+ * Returns true if the timestamp is within from..=to.
+ */
+pub fn in_range(timestamp: &Timestamp, from: &Timestamp, to: &Timestamp) -> bool {
+    from <= timestamp && timestamp <= to
+}
+
+/**
+ * Parses an ISO 8601 timestamp.
+ * Timestamps without an offset (e.g. "2023-08-01T15:41:47") are taken as UTC.
+ */
+#[cfg(feature = "chrono")]
+pub fn parse(s: &str) -> Option<Timestamp> {
+    use chrono::{DateTime, NaiveDateTime, Utc};
+
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()
+        .map(|naive| naive.and_utc())
+}
+
+/**
+ * serde (de)serialization of Timestamp as RFC 3339 strings.
+ * Used with #[serde(with = "crate::timestamp::serde_timestamp")].
+ */
+#[cfg(feature = "chrono")]
+pub mod serde_timestamp {
+    use chrono::SecondsFormat;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Timestamp;
+
+    pub fn serialize<S: Serializer>(
+        timestamp: &Timestamp,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Timestamp, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        match super::parse(&s) {
+            Some(timestamp) => Ok(timestamp),
+            None => Err(serde::de::Error::custom(format!(
+                "invalid timestamp: {}",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "chrono"))]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let a = parse("2023-08-01T15:41:47Z").unwrap();
+        let b = parse("2023-08-01T19:10:56.045522Z").unwrap();
+        let c = parse("2023-08-01T15:41:47").unwrap();
+        let d = parse("2023-08-01T17:41:47+02:00").unwrap();
+        assert_eq!(a, Utc.with_ymd_and_hms(2023, 8, 1, 15, 41, 47).unwrap());
+        assert!(a < b);
+        assert_eq!(a, c);
+        assert_eq!(a, d);
+        assert!(in_range(&a, &a, &b));
+        assert!(in_range(&d, &(b - Duration::days(30)), &b));
+        assert!(parse("yesterday").is_none());
+        assert!(parse("2023-02-29T00:00:00Z").is_none());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct S {
+            #[serde(with = "serde_timestamp")]
+            t: Timestamp,
+        }
+        for s in ["2023-08-01T15:41:47Z", "2023-08-01T19:10:56.045522Z"] {
+            let json = format!("{{\"t\":\"{}\"}}", s);
+            let v: S = serde_json::from_str(&json).unwrap();
+            assert_eq!(serde_json::to_string(&v).unwrap(), json);
+        }
+        assert!(serde_json::from_str::<S>("{\"t\":\"not a timestamp\"}").is_err());
+    }
+}
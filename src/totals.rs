@@ -1,40 +1,281 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::errors::Error;
 
 /**
- * Totals is a struct that represents the totals for a commit.
+ * Totals is a struct that represents the totals for a commit, a report or a file.
+ * The API omits some fields or sends null depending on the endpoint,
+ * so missing and null values are read as zero.
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct Totals {
+    #[serde(default, deserialize_with = "null_as_default")]
     pub files: usize,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub lines: usize,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub hits: usize,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub misses: usize,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub partials: usize,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub coverage: f64,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub branches: usize,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub methods: usize,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub messages: usize,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub sessions: usize,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub complexity: f64,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub complexity_total: f64,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub complexity_ratio: f64,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub diff: Diff,
 }
 
 /**
  * Diff is a struct that represents the diff for a commit.
  * Diff may be a u64 or an array of Option<String> like this:
- * "diff": [0, 0, 0, 0, "81.81818", null, 0, 0, 0, 0, "84.5", null, 0]
+ * "diff": [1, 11, 9, 2, 0, "81.81818", 0, 0, 0, 0, "84.5", null, 0]
  */
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum Diff {
     Value(u64),
     Array(Vec<Option<DiffValue>>),
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum DiffValue {
     NumValue(u64),
+    FloatValue(f64),
     StringValue(String),
 }
+
+impl Default for Diff {
+    fn default() -> Self {
+        Diff::Value(0)
+    }
+}
+
+impl Diff {
+    /**
+     * Returns the positional values if this is the array form.
+     */
+    pub fn as_array(&self) -> Option<&[Option<DiffValue>]> {
+        match self {
+            Diff::Value(_) => None,
+            Diff::Array(values) => Some(values),
+        }
+    }
+
+    /**
+     * Returns the value at index as a number, parsing numeric strings like "81.81818".
+     * Returns None for the u64 form, out of range indexes and null values.
+     */
+    pub fn get_f64(&self, index: usize) -> Option<f64> {
+        self.as_array()?.get(index)?.as_ref()?.as_f64()
+    }
+
+    /**
+     * Decodes the array form into PatchTotals. Returns None for the u64 form.
+     * Missing and null counts are read as zero; counts that are not non-negative integers
+     * are an Error::FormatError.
+     */
+    pub fn patch_totals(&self) -> Result<Option<PatchTotals>, Error> {
+        let values = match self.as_array() {
+            Some(values) => values,
+            None => return Ok(None),
+        };
+        let count = |index: usize| match values.get(index) {
+            None | Some(None) => Ok(0),
+            Some(Some(value)) => match value.as_count() {
+                Some(count) => Ok(count),
+                None => Err(Error::FormatError(format!(
+                    "invalid count {:?} at index {} of diff",
+                    value, index
+                ))),
+            },
+        };
+        Ok(Some(PatchTotals {
+            files: count(0)?,
+            lines: count(1)?,
+            hits: count(2)?,
+            misses: count(3)?,
+            partials: count(4)?,
+            coverage: self.get_f64(5),
+            branches: count(6)?,
+            methods: count(7)?,
+            messages: count(8)?,
+            sessions: count(9)?,
+            complexity: self.get_f64(10),
+            complexity_total: self.get_f64(11),
+        }))
+    }
+}
+
+/**
+ * PatchTotals is the decoded form of the Diff array, i.e. the totals of the lines changed by a commit.
+ * The array is positional: [files, lines, hits, misses, partials, coverage, branches,
+ * methods, messages, sessions, complexity, complexity_total, diff].
+ * coverage and complexity are None when the API sends null (e.g. no covered lines were changed).
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PatchTotals {
+    pub files: usize,
+    pub lines: usize,
+    pub hits: usize,
+    pub misses: usize,
+    pub partials: usize,
+    pub coverage: Option<f64>,
+    pub branches: usize,
+    pub methods: usize,
+    pub messages: usize,
+    pub sessions: usize,
+    pub complexity: Option<f64>,
+    pub complexity_total: Option<f64>,
+}
+
+impl Totals {
+    /**
+     * Returns the patch totals decoded from the diff field, if the API sent the array form.
+     */
+    pub fn patch(&self) -> Result<Option<PatchTotals>, Error> {
+        self.diff.patch_totals()
+    }
+}
+
+impl DiffValue {
+    /**
+     * Returns the value as a number, parsing numeric strings.
+     */
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            DiffValue::NumValue(value) => Some(*value as f64),
+            DiffValue::FloatValue(value) => Some(*value),
+            DiffValue::StringValue(value) => value.trim().parse::<f64>().ok(),
+        }
+    }
+
+    /**
+     * Returns the value as a count, i.e. a non-negative integer or a string of one.
+     */
+    pub fn as_count(&self) -> Option<usize> {
+        match self {
+            DiffValue::NumValue(value) => usize::try_from(*value).ok(),
+            DiffValue::FloatValue(_) => None,
+            DiffValue::StringValue(value) => value.trim().parse::<usize>().ok(),
+        }
+    }
+}
+
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_shapes() {
+        let commit_totals = json!({
+            "files": 10,
+            "lines": 172,
+            "hits": 148,
+            "misses": 23,
+            "partials": 1,
+            "coverage": 86.05,
+            "branches": 22,
+            "methods": 0,
+            "sessions": 1,
+            "complexity": 0.0,
+            "complexity_total": 0.0,
+            "complexity_ratio": 0,
+            "diff": [1, 11, 9, 2, 0, "81.81818", 0, 0, 0, 0, "84.5", null, 0]
+        });
+        let totals = serde_json::from_value::<Totals>(commit_totals).unwrap();
+        assert_eq!(totals.messages, 0);
+        assert_eq!(totals.complexity_ratio, 0.0);
+        assert_eq!(totals.diff.get_f64(5), Some(81.81818));
+        assert_eq!(totals.diff.get_f64(11), None);
+        assert_eq!(totals.diff.get_f64(100), None);
+
+        let null_totals = json!({
+            "files": 1,
+            "lines": 0,
+            "coverage": null,
+            "complexity": null,
+            "diff": null,
+            "messages": 3
+        });
+        let totals = serde_json::from_value::<Totals>(null_totals).unwrap();
+        assert_eq!(totals.coverage, 0.0);
+        assert_eq!(totals.diff, Diff::Value(0));
+        assert_eq!(totals.messages, 3);
+        assert_eq!(totals.diff.get_f64(0), None);
+    }
+
+    #[test]
+    fn test_patch_totals() {
+        let diff = serde_json::from_value::<Diff>(json!([
+            1, 11, 9, 2, 0, "81.81818", 0, 0, 0, 0, "84.5", null, 0
+        ]))
+        .unwrap();
+        let patch = diff.patch_totals().unwrap().unwrap();
+        assert_eq!(patch.lines, 11);
+        assert_eq!(patch.coverage, Some(81.81818));
+        assert_eq!(patch.partials, 0);
+        assert_eq!(patch.complexity, Some(84.5));
+        assert_eq!(patch.complexity_total, None);
+
+        let diff = serde_json::from_value::<Diff>(json!([
+            1, 3, 1, 1, 1, "33.33333", 0, 0, 0, 1, null, null, 0
+        ]))
+        .unwrap();
+        let patch = diff.patch_totals().unwrap().unwrap();
+        assert_eq!(patch.files, 1);
+        assert_eq!(patch.lines, 3);
+        assert_eq!(patch.hits, 1);
+        assert_eq!(patch.misses, 1);
+        assert_eq!(patch.partials, 1);
+        assert_eq!(patch.coverage, Some(33.33333));
+        assert_eq!(patch.sessions, 1);
+        assert_eq!(patch.complexity, None);
+
+        let diff = serde_json::from_value::<Diff>(json!([0, 0, 0, 0, 0, null])).unwrap();
+        let patch = diff.patch_totals().unwrap().unwrap();
+        assert_eq!(patch.lines, 0);
+        assert_eq!(patch.coverage, None);
+        assert_eq!(patch.sessions, 0);
+
+        for invalid in [
+            json!([0, 1.5]),
+            json!([0, -1]),
+            json!([0, "2.5"]),
+            json!([0, "x"]),
+        ] {
+            let diff = serde_json::from_value::<Diff>(invalid).unwrap();
+            assert!(matches!(diff.patch_totals(), Err(Error::FormatError(_))));
+        }
+
+        let totals = Totals {
+            diff: Diff::Value(0),
+            ..Default::default()
+        };
+        assert_eq!(totals.patch().unwrap(), None);
+    }
+}
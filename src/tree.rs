@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::errors::Error;
+use crate::totals::Totals;
+
+/**
+ * CoverageTree is a directory tree of a report, where every node has the aggregated Totals of the files below it.
+ * Files are leaves. The root has an empty name and path.
+ * Coverage of a directory is recomputed from hits / (hits + misses + partials), not averaged over files.
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoverageTree {
+    pub name: String,
+    pub path: String,
+    pub totals: Totals,
+    pub children: BTreeMap<String, CoverageTree>,
+}
+
+impl CoverageTree {
+    /**
+     * Builds a tree from file paths and their totals.
+     * Every file counts as one file regardless of the files field of its totals.
+     * A path that is both a file and a directory (e.g. "a" and "a/b.rs") is an Error::FormatError,
+     * since the totals of the file would be lost when the directory is aggregated.
+     */
+    pub fn from_files<'a>(
+        files: impl IntoIterator<Item = (&'a str, &'a Totals)>,
+    ) -> Result<CoverageTree, Error> {
+        let mut root = CoverageTree::default();
+        let mut file_paths = BTreeSet::new();
+        for (path, totals) in files {
+            let mut node = &mut root;
+            let mut node_path = String::new();
+            for component in path.split('/').filter(|c| !c.is_empty()) {
+                if file_paths.contains(&node_path) {
+                    return Err(collision(&node_path));
+                }
+                if !node_path.is_empty() {
+                    node_path.push('/');
+                }
+                node_path.push_str(component);
+                node = node
+                    .children
+                    .entry(component.to_string())
+                    .or_insert_with(|| CoverageTree {
+                        name: component.to_string(),
+                        path: node_path.clone(),
+                        ..Default::default()
+                    });
+            }
+            if !node.is_file() {
+                return Err(collision(&node_path));
+            }
+            let mut totals = totals.clone();
+            totals.files = 1;
+            add(&mut node.totals, &totals);
+            file_paths.insert(node_path);
+        }
+        root.aggregate();
+        Ok(root)
+    }
+
+    /**
+     * Returns true if the node has no children, i.e. it is a file.
+     */
+    pub fn is_file(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /**
+     * Returns the node at the given path, e.g. "src/formats". "" returns the root.
+     */
+    pub fn get(&self, path: &str) -> Option<&CoverageTree> {
+        let mut node = self;
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            node = node.children.get(component)?;
+        }
+        Some(node)
+    }
+
+    /**
+     * Returns the nodes in depth-first order with their depth, down to max_depth.
+     * The node itself is at depth 0, so walk(1) returns it and its direct children.
+     */
+    pub fn walk(&self, max_depth: usize) -> Vec<(usize, &CoverageTree)> {
+        let mut nodes = vec![];
+        self.walk_into(0, max_depth, &mut nodes);
+        nodes
+    }
+
+    fn walk_into<'a>(
+        &'a self,
+        depth: usize,
+        max_depth: usize,
+        nodes: &mut Vec<(usize, &'a CoverageTree)>,
+    ) {
+        nodes.push((depth, self));
+        if depth >= max_depth {
+            return;
+        }
+        for child in self.children.values() {
+            child.walk_into(depth + 1, max_depth, nodes);
+        }
+    }
+
+    fn aggregate(&mut self) {
+        if self.is_file() {
+            self.totals.coverage = coverage(&self.totals);
+            return;
+        }
+        let mut totals = Totals::default();
+        for child in self.children.values_mut() {
+            child.aggregate();
+            add(&mut totals, &child.totals);
+        }
+        totals.coverage = coverage(&totals);
+        self.totals = totals;
+    }
+}
+
+fn collision(path: &str) -> Error {
+    Error::FormatError(format!("{} is both a file and a directory", path))
+}
+
+fn add(totals: &mut Totals, other: &Totals) {
+    totals.files += other.files;
+    totals.lines += other.lines;
+    totals.hits += other.hits;
+    totals.misses += other.misses;
+    totals.partials += other.partials;
+    totals.branches += other.branches;
+    totals.methods += other.methods;
+    totals.messages += other.messages;
+    totals.sessions = totals.sessions.max(other.sessions);
+    totals.complexity += other.complexity;
+    totals.complexity_total += other.complexity_total;
+    totals.complexity_ratio = if totals.complexity_total > 0.0 {
+        totals.complexity / totals.complexity_total * 100.0
+    } else {
+        0.0
+    };
+}
+
+fn coverage(totals: &Totals) -> f64 {
+    let lines = totals.hits + totals.misses + totals.partials;
+    if lines == 0 {
+        return 0.0;
+    }
+    totals.hits as f64 / lines as f64 * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals(hits: usize, misses: usize, partials: usize) -> Totals {
+        Totals {
+            lines: hits + misses + partials,
+            hits,
+            misses,
+            partials,
+            coverage: 12.3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_from_files() {
+        let lib = totals(9, 1, 0);
+        let codecov_json = totals(1, 0, 0);
+        let llvm_cov = totals(0, 1, 1);
+        let readme = totals(0, 0, 0);
+        let tree = CoverageTree::from_files(vec![
+            ("src/lib.rs", &lib),
+            ("src/formats/codecov_json.rs", &codecov_json),
+            ("src/formats/llvm_cov.rs", &llvm_cov),
+            ("README.md", &readme),
+        ])
+        .unwrap();
+        assert_eq!(tree.totals.files, 4);
+        assert_eq!(tree.totals.hits, 10);
+        assert_eq!(tree.totals.coverage, 10.0 / 13.0 * 100.0);
+
+        let formats = tree.get("src/formats/").unwrap();
+        assert_eq!(formats.path, "src/formats");
+        assert_eq!(formats.totals.files, 2);
+        assert_eq!(formats.totals.coverage, 1.0 / 3.0 * 100.0);
+        assert!(!formats.is_file());
+
+        let lib = tree.get("src/lib.rs").unwrap();
+        assert!(lib.is_file());
+        assert_eq!(lib.totals.coverage, 90.0);
+        assert_eq!(tree.get("README.md").unwrap().totals.coverage, 0.0);
+        assert!(tree.get("src/main.rs").is_none());
+        assert_eq!(tree.get("").unwrap(), &tree);
+    }
+
+    #[test]
+    fn test_walk() {
+        let t = totals(1, 0, 0);
+        let tree =
+            CoverageTree::from_files(vec![("a/b/c.rs", &t), ("a/d.rs", &t), ("e.rs", &t)]).unwrap();
+        let paths: Vec<(usize, &str)> = tree
+            .walk(2)
+            .into_iter()
+            .map(|(depth, node)| (depth, node.path.as_str()))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![(0, ""), (1, "a"), (2, "a/b"), (2, "a/d.rs"), (1, "e.rs")]
+        );
+        assert_eq!(tree.walk(usize::MAX).len(), 6);
+        assert_eq!(tree.get("a").unwrap().walk(0).len(), 1);
+    }
+
+    #[test]
+    fn test_file_and_directory_collision() {
+        let t = totals(1, 0, 0);
+        for files in [
+            vec![("a", &t), ("a/b.rs", &t)],
+            vec![("a/b.rs", &t), ("a", &t)],
+            vec![("src/a", &t), ("src/a/b/c.rs", &t)],
+        ] {
+            assert!(matches!(
+                CoverageTree::from_files(files),
+                Err(Error::FormatError(_))
+            ));
+        }
+        let tree = CoverageTree::from_files(vec![("a.rs", &t), ("a/b.rs", &t)]).unwrap();
+        assert_eq!(tree.totals.files, 2);
+    }
+}
@@ -0,0 +1,145 @@
+/**
+ * Codecov v2 API
+ * /commits/{commitid}/uploads endpoint returns the uploads (sessions) received for a commit.
+ */
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::timestamp::Timestamp;
+use crate::totals::Totals;
+use crate::url::Url;
+
+/**
+ * UploadsAPIResponse is a struct that represents the response from the uploads API.
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UploadsAPIResponse {
+    pub results: Vec<Upload>,
+    pub count: usize,
+    pub next: Option<Url>,
+    pub previous: Option<Url>,
+    pub total_pages: usize,
+}
+
+/**
+ * Upload is a struct that represents a single upload of a coverage report for a commit.
+ * totals is None until the upload is processed.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Upload {
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub flags: Vec<String>,
+    pub provider: Option<String>,
+    pub build_url: Option<String>,
+    pub build_code: Option<String>,
+    pub job_code: Option<String>,
+    pub name: Option<String>,
+    pub state: UploadState,
+    #[cfg_attr(feature = "chrono", serde(with = "crate::timestamp::serde_timestamp"))]
+    pub created_at: Timestamp,
+    pub totals: Option<Totals>,
+}
+
+/**
+ * UploadState is the processing state of an upload.
+ * States this crate does not know yet are read as Unknown.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadState {
+    Started,
+    Uploaded,
+    Processed,
+    Error,
+    FullyOverwritten,
+    PartiallyOverwritten,
+    #[serde(other)]
+    Unknown,
+}
+
+/**
+ * Returns the expected flags that no upload carries, e.g. to detect CI shards that did not upload.
+ * Uploads in the error state are not counted.
+ */
+pub fn missing_flags(uploads: &[Upload], expected: &[&str]) -> Vec<String> {
+    expected
+        .iter()
+        .filter(|flag| {
+            !uploads.iter().any(|upload| {
+                upload.state != UploadState::Error && upload.flags.iter().any(|f| f == *flag)
+            })
+        })
+        .map(|flag| flag.to_string())
+        .collect()
+}
+
+fn null_as_empty<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(Option::<Vec<String>>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_uploads() {
+        let j = json!({
+            "count": 3,
+            "next": null,
+            "previous": null,
+            "total_pages": 1,
+            "results": [
+                {
+                    "id": 1,
+                    "created_at": "2023-08-01T15:41:47Z",
+                    "updated_at": "2023-08-01T15:42:00Z",
+                    "storage_path": "v4/raw/...",
+                    "flags": ["unit", "linux"],
+                    "provider": "github-actions",
+                    "build_code": "5731123",
+                    "name": null,
+                    "job_code": null,
+                    "build_url": "https://github.com/kitsuyui/rust-codecov/actions/runs/5731123",
+                    "state": "processed",
+                    "env": null,
+                    "upload_type": "uploaded",
+                    "totals": {"files": 10, "lines": 172, "hits": 148, "misses": 23, "partials": 1, "coverage": 86.05, "branches": 22, "methods": 0}
+                },
+                {
+                    "created_at": "2023-08-01T15:43:00Z",
+                    "flags": null,
+                    "provider": null,
+                    "build_code": null,
+                    "name": null,
+                    "job_code": null,
+                    "build_url": null,
+                    "state": "uploaded",
+                    "totals": null
+                },
+                {
+                    "created_at": "2023-08-01T15:44:00Z",
+                    "flags": ["integration"],
+                    "provider": "github-actions",
+                    "build_code": null,
+                    "name": null,
+                    "job_code": null,
+                    "build_url": null,
+                    "state": "error",
+                    "totals": null
+                }
+            ]
+        });
+        let response = serde_json::from_value::<UploadsAPIResponse>(j).unwrap();
+        let uploads = response.results;
+        assert_eq!(uploads[0].flags, vec!["unit", "linux"]);
+        assert_eq!(uploads[0].state, UploadState::Processed);
+        assert_eq!(uploads[0].totals.as_ref().unwrap().lines, 172);
+        assert!(uploads[1].flags.is_empty());
+        assert!(uploads[1].totals.is_none());
+        assert_eq!(
+            missing_flags(&uploads, &["unit", "linux", "integration", "windows"]),
+            vec!["integration", "windows"]
+        );
+    }
+}
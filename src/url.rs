@@ -1,4 +1,71 @@
+use crate::errors::Error;
+
 /**
- * Url is just a string that represents a URL. (currently)
+ * Url is a parsed URL (re-exported from the url crate).
+ * Pagination links (next, previous) in API responses are deserialized into it.
  */
-pub type Url = String;
+pub use ::url::Url;
+
+/**
+ * API_ENDPOINT is the base URL of the Codecov v2 API.
+ */
+pub const API_ENDPOINT: &str = "https://codecov.io/api/v2";
+
+/**
+ * Returns API_ENDPOINT with the given path segments appended.
+ * Every segment is percent-encoded, so "feature/foo" stays a single segment.
+ */
+pub fn api_url(segments: &[&str]) -> Result<Url, Error> {
+    let base = match Url::parse(API_ENDPOINT) {
+        Ok(base) => base,
+        Err(e) => return Err(Error::UrlError(e)),
+    };
+    join_segments(&base, segments)
+}
+
+/**
+ * Returns a copy of base with the given path segments appended, percent-encoding each one.
+ */
+pub fn join_segments(base: &Url, segments: &[&str]) -> Result<Url, Error> {
+    let mut url = base.clone();
+    match url.path_segments_mut() {
+        Ok(mut path) => {
+            path.pop_if_empty().extend(segments);
+        }
+        Err(_) => return Err(Error::ParseError(format!("cannot be a base URL: {}", base))),
+    };
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_url() {
+        let url = api_url(&["github", "kitsuyui", "repos", "rust-codecov"]).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://codecov.io/api/v2/github/kitsuyui/repos/rust-codecov"
+        );
+    }
+
+    #[test]
+    fn test_join_segments_encodes() {
+        let base = api_url(&["github", "kitsuyui", "repos", "rust-codecov"]).unwrap();
+        let url = join_segments(&base, &["branches", "feature/foo#1"]).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://codecov.io/api/v2/github/kitsuyui/repos/rust-codecov/branches/feature%2Ffoo%231"
+        );
+        let url = join_segments(&base, &["file_report", "src/my file.rs"]).unwrap();
+        assert_eq!(
+            url.path_segments().unwrap().next_back(),
+            Some("src%2Fmy%20file.rs")
+        );
+
+        let base = Url::parse("https://codecov.io/api/v2/").unwrap();
+        let url = join_segments(&base, &["github"]).unwrap();
+        assert_eq!(url.as_str(), "https://codecov.io/api/v2/github");
+    }
+}
@@ -0,0 +1,249 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::api::CodecovApi;
+use crate::author::Author;
+use crate::commits::Commit;
+use crate::errors::Error;
+use crate::Client;
+
+/**
+ * CANCEL_CHECK_INTERVAL is how often cancel is checked while waiting between polls.
+ */
+pub const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/**
+ * WaitOptions controls how Client::wait_for_commit polls.
+ * The interval starts at initial_interval and doubles after every poll up to max_interval.
+ * cancel is called before every poll and every CANCEL_CHECK_INTERVAL while waiting;
+ * waiting stops with Error::CancelledError when it returns true.
+ */
+#[derive(Clone)]
+pub struct WaitOptions {
+    pub timeout: Duration,
+    pub initial_interval: Duration,
+    pub max_interval: Duration,
+    pub cancel: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
+}
+
+impl WaitOptions {
+    pub fn new(timeout: Duration) -> WaitOptions {
+        WaitOptions {
+            timeout,
+            initial_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(30),
+            cancel: None,
+        }
+    }
+
+    pub fn with_intervals(mut self, initial: Duration, max: Duration) -> WaitOptions {
+        self.initial_interval = initial;
+        self.max_interval = max;
+        self
+    }
+
+    pub fn with_cancel(mut self, cancel: impl Fn() -> bool + Send + Sync + 'static) -> WaitOptions {
+        self.cancel = Some(Arc::new(cancel));
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        match &self.cancel {
+            Some(cancel) => cancel(),
+            None => false,
+        }
+    }
+}
+
+impl Client {
+    /**
+     * Polls a commit until Codecov has finished processing it (complete, error or skipped).
+     * A commit that is not found yet (e.g. right after the upload) is polled again.
+     * Returns Error::TimeoutError when the commit is still pending after timeout.
+     */
+    pub fn wait_for_commit(
+        &self,
+        author: &Author,
+        commitid: &str,
+        timeout: Duration,
+    ) -> Result<Commit, Error> {
+        self.wait_for_commit_with(author, commitid, &WaitOptions::new(timeout))
+    }
+
+    /**
+     * wait_for_commit with a custom backoff and a cancellation hook.
+     */
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(repo = %author, commitid = commitid, retries = tracing::field::Empty))
+    )]
+    pub fn wait_for_commit_with(
+        &self,
+        author: &Author,
+        commitid: &str,
+        options: &WaitOptions,
+    ) -> Result<Commit, Error> {
+        CodecovApi::wait_for_commit_with(self, author, commitid, options)
+    }
+}
+
+pub(crate) fn poll(
+    mut fetch: impl FnMut() -> Result<Commit, Error>,
+    options: &WaitOptions,
+    mut sleep: impl FnMut(Duration),
+) -> Result<Commit, Error> {
+    let started = Instant::now();
+    let mut interval = options.initial_interval;
+    #[cfg(feature = "tracing")]
+    let mut retries: u64 = 0;
+    loop {
+        if options.is_cancelled() {
+            return Err(Error::CancelledError);
+        }
+        let last_state = match fetch() {
+            Ok(commit) if commit.state.is_finished() => return Ok(commit),
+            Ok(commit) => commit.state.to_string(),
+            Err(Error::NotFoundError(_)) => "not found".to_string(),
+            Err(e) => return Err(e),
+        };
+        let elapsed = started.elapsed();
+        if elapsed >= options.timeout {
+            return Err(Error::TimeoutError(format!(
+                "commit is still {} after {:?}",
+                last_state, elapsed
+            )));
+        }
+        wait(interval.min(options.timeout - elapsed), options, &mut sleep)?;
+        interval = (interval * 2).min(options.max_interval);
+        #[cfg(feature = "tracing")]
+        {
+            retries += 1;
+            tracing::Span::current().record("retries", retries);
+        }
+    }
+}
+
+/**
+ * Sleeps for duration, in slices of CANCEL_CHECK_INTERVAL when there is a cancel hook.
+ */
+fn wait(
+    duration: Duration,
+    options: &WaitOptions,
+    sleep: &mut impl FnMut(Duration),
+) -> Result<(), Error> {
+    if options.cancel.is_none() {
+        sleep(duration);
+        return Ok(());
+    }
+    let mut remaining = duration;
+    while !remaining.is_zero() {
+        if options.is_cancelled() {
+            return Err(Error::CancelledError);
+        }
+        let slice = remaining.min(CANCEL_CHECK_INTERVAL);
+        sleep(slice);
+        remaining -= slice;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::commits::CommitState;
+
+    fn commit(state: &str) -> Commit {
+        serde_json::from_value(json!({
+            "commitid": "1eb341765e7c3daa88ae5d2a751538a620c6dbce",
+            "message": "fix",
+            "timestamp": "2023-08-01T15:41:47Z",
+            "ci_passed": true,
+            "author": {"service": "github", "username": "kitsuyui", "name": null},
+            "branch": "main",
+            "totals": {},
+            "state": state,
+            "parent": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_poll_backoff() {
+        let mut responses = vec![
+            Ok(commit("complete")),
+            Ok(commit("pending")),
+            Ok(commit("pending")),
+            Err(Error::NotFoundError("Not found.".to_string())),
+        ];
+        let mut sleeps = vec![];
+        let options = WaitOptions::new(Duration::from_secs(3600))
+            .with_intervals(Duration::from_secs(1), Duration::from_secs(3));
+        let commit = poll(
+            || responses.pop().unwrap(),
+            &options,
+            |duration| sleeps.push(duration.as_secs()),
+        )
+        .unwrap();
+        assert_eq!(commit.state, CommitState::Complete);
+        assert_eq!(sleeps, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_poll_stops() {
+        let options = WaitOptions::new(Duration::ZERO);
+        let result = poll(|| Ok(commit("pending")), &options, |_| {});
+        assert!(matches!(result, Err(Error::TimeoutError(_))));
+
+        let result = poll(
+            || Err(Error::ParseError("broken".to_string())),
+            &options,
+            |_| {},
+        );
+        assert!(matches!(result, Err(Error::ParseError(_))));
+
+        let polls = Arc::new(AtomicUsize::new(0));
+        let counter = polls.clone();
+        let options = WaitOptions::new(Duration::from_secs(3600))
+            .with_cancel(move || counter.load(Ordering::SeqCst) >= 2);
+        let result = poll(
+            || {
+                polls.fetch_add(1, Ordering::SeqCst);
+                Ok(commit("pending"))
+            },
+            &options,
+            |_| {},
+        );
+        assert!(matches!(result, Err(Error::CancelledError)));
+    }
+
+    #[test]
+    fn test_cancel_during_backoff() {
+        let slept = Arc::new(AtomicUsize::new(0));
+        let counter = slept.clone();
+        // Cancelled after 3 slices, long before the 30 seconds interval is over.
+        let options = WaitOptions::new(Duration::from_secs(3600))
+            .with_intervals(Duration::from_secs(30), Duration::from_secs(30))
+            .with_cancel(move || counter.load(Ordering::SeqCst) >= 3);
+        let mut sleeps = vec![];
+        let result = poll(
+            || Ok(commit("pending")),
+            &options,
+            |duration| {
+                sleeps.push(duration);
+                slept.fetch_add(1, Ordering::SeqCst);
+            },
+        );
+        assert!(matches!(result, Err(Error::CancelledError)));
+        assert_eq!(sleeps, vec![CANCEL_CHECK_INTERVAL; 3]);
+    }
+
+    #[test]
+    fn test_options_are_send() {
+        fn assert_send_sync<T: Send + Sync>(_: T) {}
+        assert_send_sync(WaitOptions::new(Duration::ZERO).with_cancel(|| false));
+    }
+}